@@ -1,5 +1,9 @@
+mod square;
+pub use square::{File, Rank, Square, SquareError, SquareIterator};
+
 use std::{
     fmt,
+    iter::FromIterator,
     ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
 };
 
@@ -29,6 +33,10 @@ pub const FILES: [BitBoard; 8] = [
     A_FILE, B_FILE, C_FILE, D_FILE, E_FILE, F_FILE, G_FILE, H_FILE,
 ];
 
+// The a1-h8 diagonal and the a8-h1 anti-diagonal
+pub const MAIN_DIAGONAL: BitBoard = BitBoard(0x8040_2010_0804_0201);
+pub const ANTI_DIAGONAL: BitBoard = BitBoard(0x0102_0408_1020_4080);
+
 #[derive(PartialEq, PartialOrd, Clone, Copy, Debug, Default, Eq, Hash)]
 pub struct BitBoard(pub u64);
 
@@ -82,6 +90,13 @@ impl Not for BitBoard {
     }
 }
 
+impl FromIterator<Square> for BitBoard {
+    fn from_iter<I: IntoIterator<Item = Square>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(BitBoard::EMPTY, |bb, square| bb | square.bitboard())
+    }
+}
+
 impl fmt::Display for BitBoard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Binary::fmt(&self.0, f)
@@ -89,10 +104,22 @@ impl fmt::Display for BitBoard {
 }
 
 impl BitBoard {
+    pub const EMPTY: BitBoard = BitBoard(0);
+    pub const FULL: BitBoard = BitBoard(!0);
+
+    pub const fn new(bits: u64) -> BitBoard {
+        BitBoard(bits)
+    }
+
+    pub const fn from_square(square: Square) -> BitBoard {
+        square.bitboard()
+    }
+
     pub fn to_str(&self) -> String {
         format!("{:0>64}", self)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(str: &str) -> BitBoard {
         BitBoard(u64::from_str_radix(str, 2).unwrap())
     }
@@ -114,6 +141,10 @@ impl BitBoard {
         BitIterator::new(self)
     }
 
+    pub fn squares(&self) -> SquareIterator {
+        SquareIterator::new(self)
+    }
+
     pub fn popcnt(&self) -> u32 {
         self.0.count_ones()
     }
@@ -133,7 +164,7 @@ impl BitBoard {
     }
 
     pub fn get_file(&self) -> BitBoard {
-        RANKS[self.row()]
+        FILES[self.col()]
     }
 
     pub fn index(&self) -> usize {
@@ -147,6 +178,147 @@ impl BitBoard {
     pub fn is_not_empty(&self) -> bool {
         !self.is_empty()
     }
+
+    pub const fn more_than_one(&self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    pub fn contains(&self, square: Square) -> bool {
+        (*self & square.bitboard()).is_not_empty()
+    }
+
+    // Least significant square, i.e. the one closest to a1
+    pub fn lsb(&self) -> Option<Square> {
+        Square::from_index(self.0.trailing_zeros() as usize)
+    }
+
+    // Most significant square, i.e. the one closest to h8
+    pub fn msb(&self) -> Option<Square> {
+        if self.is_empty() {
+            None
+        } else {
+            Square::from_index(63 - self.0.leading_zeros() as usize)
+        }
+    }
+
+    // Removes the least significant square and returns it
+    pub fn pop_lsb(&mut self) -> Option<Square> {
+        let square = self.lsb();
+        self.0 &= self.0.wrapping_sub(1);
+        square
+    }
+
+    // One step in each direction. Squares pushed off the board are dropped rather than wrapping
+    // around to the other side
+    pub const fn north(&self) -> BitBoard {
+        BitBoard(self.0 << 8)
+    }
+
+    pub const fn south(&self) -> BitBoard {
+        BitBoard(self.0 >> 8)
+    }
+
+    pub const fn east(&self) -> BitBoard {
+        BitBoard((self.0 << 1) & !A_FILE.0)
+    }
+
+    pub const fn west(&self) -> BitBoard {
+        BitBoard((self.0 >> 1) & !H_FILE.0)
+    }
+
+    pub const fn north_east(&self) -> BitBoard {
+        BitBoard((self.0 << 9) & !A_FILE.0)
+    }
+
+    pub const fn north_west(&self) -> BitBoard {
+        BitBoard((self.0 << 7) & !H_FILE.0)
+    }
+
+    pub const fn south_east(&self) -> BitBoard {
+        BitBoard((self.0 >> 7) & !A_FILE.0)
+    }
+
+    pub const fn south_west(&self) -> BitBoard {
+        BitBoard((self.0 >> 9) & !H_FILE.0)
+    }
+
+    // Rank 1 swaps with rank 8, rank 2 with rank 7 and so on
+    pub const fn flip_vertical(&self) -> BitBoard {
+        BitBoard(self.0.swap_bytes())
+    }
+
+    // The a-file swaps with the h-file, the b-file with the g-file and so on
+    pub const fn mirror_horizontal(&self) -> BitBoard {
+        BitBoard(self.0.reverse_bits().swap_bytes())
+    }
+
+    pub const fn rotate_180(&self) -> BitBoard {
+        BitBoard(self.0.reverse_bits())
+    }
+
+    // Fills spread every square as far as the edge of the board, ignoring any pieces in the way
+    pub const fn north_fill(&self) -> BitBoard {
+        let mut bits = self.0;
+        bits |= bits << 8;
+        bits |= bits << 16;
+        bits |= bits << 32;
+        BitBoard(bits)
+    }
+
+    pub const fn south_fill(&self) -> BitBoard {
+        let mut bits = self.0;
+        bits |= bits >> 8;
+        bits |= bits >> 16;
+        bits |= bits >> 32;
+        BitBoard(bits)
+    }
+
+    pub const fn east_fill(&self) -> BitBoard {
+        BitBoard(fill_up(self.0, !A_FILE.0, 1))
+    }
+
+    pub const fn west_fill(&self) -> BitBoard {
+        BitBoard(fill_down(self.0, !H_FILE.0, 1))
+    }
+
+    // Every file holding at least one square
+    pub const fn file_fill(&self) -> BitBoard {
+        BitBoard(self.north_fill().0 | self.south_fill().0)
+    }
+
+    // Every rank holding at least one square
+    pub const fn rank_fill(&self) -> BitBoard {
+        BitBoard(self.east_fill().0 | self.west_fill().0)
+    }
+
+    // Every a1-h8 diagonal holding at least one square
+    pub const fn diagonal_fill(&self) -> BitBoard {
+        BitBoard(fill_up(self.0, !A_FILE.0, 9) | fill_down(self.0, !H_FILE.0, 9))
+    }
+
+    // Every a8-h1 diagonal holding at least one square
+    pub const fn anti_diagonal_fill(&self) -> BitBoard {
+        BitBoard(fill_up(self.0, !H_FILE.0, 7) | fill_down(self.0, !A_FILE.0, 7))
+    }
+}
+
+// Kogge-Stone fill towards h8, in steps of #shift. #mask holds the squares a single step may land
+// on, which keeps the fill from wrapping around the edge of the board
+const fn fill_up(mut bits: u64, mut mask: u64, shift: u32) -> u64 {
+    bits |= mask & (bits << shift);
+    mask &= mask << shift;
+    bits |= mask & (bits << (shift * 2));
+    mask &= mask << (shift * 2);
+    bits | (mask & (bits << (shift * 4)))
+}
+
+// Kogge-Stone fill towards a1, see #fill_up
+const fn fill_down(mut bits: u64, mut mask: u64, shift: u32) -> u64 {
+    bits |= mask & (bits >> shift);
+    mask &= mask >> shift;
+    bits |= mask & (bits >> (shift * 2));
+    mask &= mask >> (shift * 2);
+    bits | (mask & (bits >> (shift * 4)))
 }
 
 pub struct BitIterator {
@@ -277,10 +449,150 @@ mod tests {
         fn it_works() {
             let mut total: u64 = 0;
             let bb = BitBoard(18374686479671623680);
-            bb.bits().into_iter().for_each(|bit| {
+            bb.bits().for_each(|bit| {
                 total += bit as u64;
             });
             assert_eq!(total, 476);
         }
     }
+
+    mod scanning {
+        use super::*;
+
+        #[test]
+        fn it_finds_the_least_and_most_significant_squares() {
+            let bb: BitBoard = vec![Square::C2, Square::F7, Square::A5]
+                .into_iter()
+                .collect();
+            assert_eq!(bb.lsb(), Some(Square::C2));
+            assert_eq!(bb.msb(), Some(Square::F7));
+            assert_eq!(BitBoard::EMPTY.lsb(), None);
+            assert_eq!(BitBoard::EMPTY.msb(), None);
+        }
+
+        #[test]
+        fn it_pops_squares_in_order() {
+            let mut bb = BitBoard::from_square(Square::H8) | BitBoard::from_square(Square::B1);
+            assert!(bb.more_than_one());
+            assert_eq!(bb.pop_lsb(), Some(Square::B1));
+            assert!(!bb.more_than_one());
+            assert_eq!(bb.pop_lsb(), Some(Square::H8));
+            assert_eq!(bb.pop_lsb(), None);
+            assert_eq!(bb, BitBoard::EMPTY);
+        }
+    }
+
+    mod lines {
+        use super::*;
+
+        #[test]
+        fn it_finds_the_file_of_a_square() {
+            assert_eq!(Square::C6.bitboard().get_file(), C_FILE);
+            assert_eq!(Square::H1.bitboard().get_file(), H_FILE);
+            assert_eq!(Square::A8.bitboard().get_file(), A_FILE);
+        }
+    }
+
+    mod shifts {
+        use super::*;
+
+        #[test]
+        fn it_drops_squares_shifted_off_the_board() {
+            let corners = BitBoard::new(0x8100_0000_0000_0081);
+            assert_eq!(corners.north(), BitBoard::new(0x81 << 8));
+            assert_eq!(corners.south(), BitBoard::new(0x81 << 48));
+            assert_eq!(
+                corners.east(),
+                Square::B1.bitboard() | Square::B8.bitboard()
+            );
+            assert_eq!(
+                corners.west(),
+                Square::G1.bitboard() | Square::G8.bitboard()
+            );
+            assert_eq!(corners.north_east(), Square::B2.bitboard());
+            assert_eq!(corners.north_west(), Square::G2.bitboard());
+            assert_eq!(corners.south_east(), Square::B7.bitboard());
+            assert_eq!(corners.south_west(), Square::G7.bitboard());
+        }
+
+        #[test]
+        fn it_flips_and_mirrors() {
+            let bb = Square::B3.bitboard();
+            assert_eq!(bb.flip_vertical(), Square::B6.bitboard());
+            assert_eq!(bb.mirror_horizontal(), Square::G3.bitboard());
+            assert_eq!(bb.rotate_180(), Square::G6.bitboard());
+            assert_eq!(RANK_2.flip_vertical(), RANK_7);
+            assert_eq!(A_FILE.mirror_horizontal(), H_FILE);
+        }
+    }
+
+    mod fills {
+        use super::*;
+
+        #[test]
+        fn it_fills_files_and_ranks() {
+            let bb = Square::C3.bitboard();
+            assert_eq!(bb.north_fill(), C_FILE & !(RANK_1 | RANK_2));
+            assert_eq!(bb.south_fill(), C_FILE & (RANK_1 | RANK_2 | RANK_3));
+            assert_eq!(bb.east_fill(), RANK_3 & !(A_FILE | B_FILE));
+            assert_eq!(bb.west_fill(), RANK_3 & (A_FILE | B_FILE | C_FILE));
+            assert_eq!(bb.file_fill(), C_FILE);
+            assert_eq!(bb.rank_fill(), RANK_3);
+        }
+
+        #[test]
+        fn it_fills_diagonals() {
+            assert_eq!(Square::D4.bitboard().diagonal_fill(), MAIN_DIAGONAL);
+            assert_eq!(Square::E4.bitboard().anti_diagonal_fill(), ANTI_DIAGONAL);
+            assert_eq!(Square::B1.bitboard().diagonal_fill(), MAIN_DIAGONAL.east());
+        }
+    }
+
+    mod square {
+        use super::*;
+        use std::convert::TryFrom;
+
+        #[test]
+        fn it_parses_and_displays_notation() {
+            let square = "e4".parse::<Square>().unwrap();
+            assert_eq!(square, Square::E4);
+            assert_eq!(square.file(), File::E);
+            assert_eq!(square.rank(), Rank::Fourth);
+            assert_eq!(square.to_string(), "e4");
+            assert_eq!(
+                "e9".parse::<Square>(),
+                Err(SquareError::InvalidNotation(String::from("e9")))
+            );
+        }
+
+        #[test]
+        fn it_converts_to_and_from_bitboards() {
+            assert_eq!(BitBoard::from(Square::A1), BitBoard(1));
+            assert_eq!(Square::H8.bitboard(), BitBoard(1 << 63));
+            assert_eq!(Square::try_from(BitBoard(1 << 28)), Ok(Square::E4));
+            assert_eq!(
+                Square::try_from(BitBoard(3)),
+                Err(SquareError::NotASingleSquare(BitBoard(3)))
+            );
+        }
+
+        #[test]
+        fn it_offsets_within_the_board() {
+            assert_eq!(Square::E4.offset(1, 2), Some(Square::F6));
+            assert_eq!(Square::H1.offset(1, 0), None);
+            assert_eq!(Square::A8.offset(0, 1), None);
+        }
+
+        #[test]
+        fn it_iterates_squares() {
+            assert_eq!(Square::all().count(), 64);
+            assert_eq!(Square::all().next(), Some(Square::A1));
+            assert_eq!(
+                BitBoard(0x8000_0000_0000_0101)
+                    .squares()
+                    .collect::<Vec<Square>>(),
+                vec![Square::A1, Square::A2, Square::H8]
+            );
+        }
+    }
 }
@@ -0,0 +1,52 @@
+use crate::bitboard::*;
+use crate::constants::*;
+use crate::piece::Pieces;
+use crate::psqt::{psqt, Score, MATERIAL, PHASE_WEIGHTS};
+use crate::Board;
+
+// Running sums over every piece on the board, kept up to date by every method that moves pieces so
+// the evaluation can read them instead of counting pieces. See Board#compute_accumulators
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Accumulators {
+    // Indexed by color, each from that side's own point of view
+    pub material: [Score; 2],
+    pub psqt: [Score; 2],
+    // The sum of PHASE_WEIGHTS over every piece, which promotions can take past MAX_PHASE
+    pub phase: i32,
+}
+
+impl Accumulators {
+    pub fn add(&mut self, piece: Pieces, square: Square) {
+        self.update(piece, square, 1);
+    }
+
+    pub fn remove(&mut self, piece: Pieces, square: Square) {
+        self.update(piece, square, -1);
+    }
+
+    // Material and piece placement of both sides, from white's point of view
+    pub fn score(&self) -> Score {
+        self.material[WHITE] + self.psqt[WHITE] - self.material[BLACK] - self.psqt[BLACK]
+    }
+
+    // Empty squares leave the sums alone, like Zobrist::piece leaves the hash alone
+    fn update(&mut self, piece: Pieces, square: Square, sign: i32) {
+        if let Some(piece) = piece.piece() {
+            let (color, role) = (piece.color.index(), piece.role.index());
+            self.material[color] += MATERIAL[role] * sign;
+            self.psqt[color] += psqt(piece, square) * sign;
+            self.phase += PHASE_WEIGHTS[role] * sign;
+        }
+    }
+}
+
+impl Board {
+    // The accumulators worked out from scratch. #accumulators is kept up to date incrementally, so
+    // this is only needed to initialize or verify it
+    pub fn compute_accumulators(&self) -> Accumulators {
+        Square::all().fold(Accumulators::default(), |mut accumulators, square| {
+            accumulators.add(self.get_piece_at(square), square);
+            accumulators
+        })
+    }
+}
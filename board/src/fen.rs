@@ -0,0 +1,59 @@
+use crate::piece::Color;
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FenError {
+    // Board, side to move, castle rights and en passant are required, the two clocks are optional
+    WrongFieldCount(usize),
+    WrongRankCount(usize),
+    // #rank is the rank number, 8 for the first rank of the FEN
+    InvalidRankLength { rank: usize },
+    UnknownPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastleRights(String),
+    InvalidEnPassant(String),
+    InvalidHalfMoveClock(String),
+    InvalidFullMoveNumber(String),
+    // Holds the color without a king, or with more than one
+    MissingKing(Color),
+    DuplicateKing(Color),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let color = |color: &Color| match color {
+            Color::White => "white",
+            Color::Black => "black",
+        };
+
+        match self {
+            FenError::WrongFieldCount(count) => {
+                write!(f, "Expected 4 or 6 fields, found {}", count)
+            }
+            FenError::WrongRankCount(count) => write!(f, "Expected 8 ranks, found {}", count),
+            FenError::InvalidRankLength { rank } => {
+                write!(f, "Rank {} does not describe 8 squares", rank)
+            }
+            FenError::UnknownPiece(piece) => write!(f, "Unknown piece: {}", piece),
+            FenError::InvalidSideToMove(side) => write!(f, "Invalid side to move: {}", side),
+            FenError::InvalidCastleRights(rights) => {
+                write!(f, "Invalid castle rights: {}", rights)
+            }
+            FenError::InvalidEnPassant(square) => {
+                write!(f, "Invalid en passant square: {}", square)
+            }
+            FenError::InvalidHalfMoveClock(clock) => {
+                write!(f, "Invalid half move clock: {}", clock)
+            }
+            FenError::InvalidFullMoveNumber(number) => {
+                write!(f, "Invalid full move number: {}", number)
+            }
+            FenError::MissingKing(side) => write!(f, "Missing {} king", color(side)),
+            FenError::DuplicateKing(side) => write!(f, "More than one {} king", color(side)),
+        }
+    }
+}
+
+impl Error for FenError {}
@@ -1,20 +1,39 @@
 extern crate bitboard;
 use crate::bitboard::*;
 extern crate piece;
-use piece::Pieces;
+use piece::{Color, Piece, Pieces};
 extern crate square;
-use square::Square;
+use square::SquareContents;
+extern crate chessmove;
 extern crate constants;
 use crate::constants::*;
+extern crate magic;
+use crate::magic::tables::PAWN_ATTACKS;
+extern crate psqt;
+mod accumulators;
+mod fen;
+mod make_move;
+mod see;
+mod validate;
+mod zobrist;
+pub use accumulators::Accumulators;
+pub use fen::FenError;
+pub use make_move::{castling_rook_move, UndoInfo};
+pub use validate::PositionError;
+pub use zobrist::Zobrist;
 
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Board {
     pub piece_bbs: [[BitBoard; 6]; 2],
+    // The piece on every square, indexed by Square. Kept in sync with the bitboards by every
+    // method that moves pieces, see #is_mailbox_consistent
+    pub mailbox: [Pieces; 64],
     pub color_bbs: [BitBoard; 2],
     pub combined_bbs: [BitBoard; 8],
-    pub side_to_move: usize,
+    pub side_to_move: Color,
     pub checkers: BitBoard,
     pub pinned: BitBoard,
     pub en_passant: BitBoard,
@@ -22,6 +41,10 @@ pub struct Board {
     pub half_moves_since_action: u8,
     pub full_moves: u16,
     pub attacked_squares: BitBoard,
+    pub hash: u64,
+    // Zobrist hash of the pawns alone, kept up to date alongside #hash
+    pub pawn_hash: u64,
+    pub accumulators: Accumulators,
 }
 
 pub struct BoardParams {
@@ -37,7 +60,7 @@ pub struct BoardParams {
     pub black_rooks: BitBoard,
     pub black_queens: BitBoard,
     pub black_kings: BitBoard,
-    pub side_to_move: Option<usize>,
+    pub side_to_move: Option<Color>,
     pub castle_rights: Option<BitBoard>,
     pub en_passant: Option<BitBoard>,
     pub half_moves_since_action: Option<u8>,
@@ -59,7 +82,7 @@ impl Default for BoardParams {
             black_rooks: INITIAL_BLACK_ROOKS,
             black_queens: INITIAL_BLACK_QUEENS,
             black_kings: INITIAL_BLACK_KINGS,
-            side_to_move: Some(WHITE),
+            side_to_move: Some(Color::White),
             castle_rights: Some(INITIAL_CASTLE_RIGHTS),
             en_passant: Some(EMPTY),
             half_moves_since_action: Some(0),
@@ -136,11 +159,12 @@ impl Board {
             castle_rights &= !G8_SQUARE;
         }
 
-        Board {
+        let mut board = Board {
             piece_bbs,
+            mailbox: [Pieces::Empty; 64],
             color_bbs,
             combined_bbs,
-            side_to_move: params.side_to_move.unwrap_or(WHITE),
+            side_to_move: params.side_to_move.unwrap_or(Color::White),
             pinned: EMPTY,
             checkers: EMPTY,
             en_passant: params.en_passant.unwrap_or(EMPTY),
@@ -148,25 +172,93 @@ impl Board {
             half_moves_since_action: params.half_moves_since_action.unwrap_or(0),
             full_moves: params.full_moves.unwrap_or(1),
             attacked_squares: EMPTY,
+            hash: 0,
+            pawn_hash: 0,
+            accumulators: Accumulators::default(),
+        };
+        for square in Square::all() {
+            board.mailbox[square.index()] = board.piece_from_bitboards(square);
+        }
+        board.hash = board.compute_hash();
+        board.pawn_hash = board.compute_pawn_hash();
+        board.accumulators = board.compute_accumulators();
+
+        board
+    }
+
+    // Full Zobrist hash of the position. #hash is kept up to date incrementally by every method
+    // that changes the position, so this is only needed to initialize or verify it
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = Square::all().fold(0, |hash, square| {
+            hash ^ Zobrist::piece(self.get_piece_at(square), square)
+        });
+
+        hash ^= Zobrist::castle_rights(self.castle_rights);
+        hash ^= self.en_passant_key();
+        if self.side_to_move == Color::Black {
+            hash ^= Zobrist::side_to_move();
+        }
+
+        hash
+    }
+
+    // Full Zobrist hash of the pawns, which positions that only differ in their pieces share. Like
+    // #compute_hash, only needed to initialize or verify #pawn_hash
+    pub fn compute_pawn_hash(&self) -> u64 {
+        Square::all().fold(0, |hash, square| {
+            hash ^ pawn_key(self.get_piece_at(square), square)
+        })
+    }
+
+    // The en passant square only makes a difference to the position when a pawn can take on it,
+    // so it is left out of the hash otherwise, and positions that only differ in it count as
+    // repetitions of each other
+    fn en_passant_key(&self) -> u64 {
+        let capturer = if (self.en_passant & RANK_3).is_not_empty() {
+            BLACK
+        } else {
+            WHITE
+        };
+        let capturers = self.en_passant.lsb().map_or(EMPTY, |square| {
+            PAWN_ATTACKS[1 - capturer][square.index()] & self.piece_bbs[capturer][PAWNS_BB]
+        });
+
+        if capturers.is_empty() {
+            0
+        } else {
+            Zobrist::en_passant(self.en_passant)
         }
     }
 
-    pub fn to_array(&self) -> [[Square; 8]; 8] {
-        let mut board_array: [[Square; 8]; 8] = [[Square::default(); 8]; 8];
-        for (pos, square) in SQUARES.iter().enumerate() {
-            let rank = 7 - (pos / 8);
-            let file = pos % 8;
+    // Laid out as the board is printed, with the eighth rank first
+    pub fn to_array(&self) -> [[SquareContents; 8]; 8] {
+        let mut board_array = [[SquareContents::default(); 8]; 8];
+        for square in Square::all() {
+            let row = 7 - square.rank().index();
+            let col = square.file().index();
 
-            let piece = self.get_piece_at(*square);
-            board_array[rank][file] = Square::new(*square, piece);
+            board_array[row][col] = SquareContents::new(square, self.get_piece_at(square));
         }
 
         board_array
     }
 
-    pub fn move_piece(&mut self, from: BitBoard, to: BitBoard) -> &mut Self {
+    pub fn move_piece(&mut self, from: Square, to: Square) -> &mut Self {
         let moving_piece = self.get_piece_at(from);
         let target_piece = self.get_piece_at(to);
+
+        self.hash ^= Zobrist::piece(target_piece, to)
+            ^ Zobrist::piece(moving_piece, from)
+            ^ Zobrist::piece(moving_piece, to);
+        self.pawn_hash ^=
+            pawn_key(target_piece, to) ^ pawn_key(moving_piece, from) ^ pawn_key(moving_piece, to);
+        self.mailbox[from.index()] = Pieces::Empty;
+        self.mailbox[to.index()] = moving_piece;
+        self.accumulators.remove(target_piece, to);
+        self.accumulators.remove(moving_piece, from);
+        self.accumulators.add(moving_piece, to);
+
+        let (from, to) = (from.bitboard(), to.bitboard());
         let combined_move = from | to;
 
         let moving_piece_color_bb_index = moving_piece.color_bb_index();
@@ -196,137 +288,209 @@ impl Board {
         self
     }
 
+    pub fn remove_piece(&mut self, square: Square) -> &mut Self {
+        let piece = self.get_piece_at(square);
+        if piece == Pieces::Empty {
+            return self;
+        }
+        self.hash ^= Zobrist::piece(piece, square);
+        self.pawn_hash ^= pawn_key(piece, square);
+        self.mailbox[square.index()] = Pieces::Empty;
+        self.accumulators.remove(piece, square);
+        let square = square.bitboard();
+
+        self.piece_bbs[piece.color_bb_index()][piece.piece_by_color_bb_index()] ^= square;
+        self.color_bbs[piece.color_bb_index()] ^= square;
+        self.combined_bbs[piece.combined_color_bb_index()] ^= square;
+
+        self.combined_bbs[EMPTY_SQUARES_BB] |= square;
+        self.combined_bbs[ALL_PIECES_BB] ^= square;
+
+        self
+    }
+
+    // Places #piece on #square, which must be empty
+    pub fn put_piece(&mut self, square: Square, piece: Pieces) -> &mut Self {
+        self.hash ^= Zobrist::piece(piece, square);
+        self.pawn_hash ^= pawn_key(piece, square);
+        self.mailbox[square.index()] = piece;
+        self.accumulators.add(piece, square);
+        let square = square.bitboard();
+
+        self.piece_bbs[piece.color_bb_index()][piece.piece_by_color_bb_index()] |= square;
+        self.color_bbs[piece.color_bb_index()] |= square;
+        self.combined_bbs[piece.combined_color_bb_index()] |= square;
+
+        self.combined_bbs[EMPTY_SQUARES_BB] &= !square;
+        self.combined_bbs[ALL_PIECES_BB] |= square;
+
+        self
+    }
+
+    // Replaces the piece on #square with #promotion, which must be of the same color. Also used to
+    // turn a promoted piece back into a pawn
+    pub fn promote_piece(&mut self, square: Square, promotion: Pieces) -> &mut Self {
+        let pawn = self.get_piece_at(square);
+        self.hash ^= Zobrist::piece(pawn, square) ^ Zobrist::piece(promotion, square);
+        self.pawn_hash ^= pawn_key(pawn, square) ^ pawn_key(promotion, square);
+        self.mailbox[square.index()] = promotion;
+        self.accumulators.remove(pawn, square);
+        self.accumulators.add(promotion, square);
+        let square = square.bitboard();
+
+        self.piece_bbs[pawn.color_bb_index()][pawn.piece_by_color_bb_index()] ^= square;
+        self.combined_bbs[pawn.combined_color_bb_index()] ^= square;
+        self.piece_bbs[promotion.color_bb_index()][promotion.piece_by_color_bb_index()] |= square;
+        self.combined_bbs[promotion.combined_color_bb_index()] |= square;
+
+        self
+    }
+
+    pub fn remove_castle_rights(&mut self, castle_rights: BitBoard) -> &mut Self {
+        self.hash ^= Zobrist::castle_rights(self.castle_rights);
+        self.castle_rights &= !castle_rights;
+        self.hash ^= Zobrist::castle_rights(self.castle_rights);
+
+        self
+    }
+
+    pub fn set_en_passant(&mut self, en_passant: BitBoard) -> &mut Self {
+        self.hash ^= self.en_passant_key();
+        self.en_passant = en_passant;
+        self.hash ^= self.en_passant_key();
+
+        self
+    }
+
+    // Panics on an invalid FEN, see #try_from_fen for a fallible version
     pub fn from_fen(fen: &str) -> Board {
-        let res: Vec<String> = fen.split(' ').map(String::from).collect();
-        let mut white_pawns: BitBoard = EMPTY;
-        let mut white_knights: BitBoard = EMPTY;
-        let mut white_bishops: BitBoard = EMPTY;
-        let mut white_rooks: BitBoard = EMPTY;
-        let mut white_queens: BitBoard = EMPTY;
-        let mut white_kings: BitBoard = EMPTY;
-        let mut black_pawns: BitBoard = EMPTY;
-        let mut black_knights: BitBoard = EMPTY;
-        let mut black_bishops: BitBoard = EMPTY;
-        let mut black_rooks: BitBoard = EMPTY;
-        let mut black_queens: BitBoard = EMPTY;
-        let mut black_kings: BitBoard = EMPTY;
-        let mut side_to_move: usize = WHITE;
-        let mut en_passant: BitBoard = EMPTY;
-        let mut castle_rights: BitBoard = EMPTY;
-        let mut half_moves_since_action: u8 = 0;
-        let mut full_moves: u16 = 0;
-        res.iter()
-            .enumerate()
-            .for_each(|(part_idx, part)| match part_idx {
-                0 => {
-                    let rows: Vec<String> = part.split('/').map(String::from).collect();
-                    rows.iter().enumerate().for_each(|(row_idx, row)| {
-                        let mut empty_cols: usize = 0;
-                        row.chars().enumerate().for_each(|(col_idx, char)| {
-                            let rank = 7 - row_idx;
-                            let square_idx = (rank * 8) + col_idx + empty_cols;
-                            let square = SQUARES[square_idx];
-
-                            match char {
-                                'r' => {
-                                    black_rooks |= square;
-                                }
-                                'b' => {
-                                    black_bishops |= square;
-                                }
-                                'n' => {
-                                    black_knights |= square;
-                                }
-                                'q' => {
-                                    black_queens |= square;
-                                }
-                                'k' => {
-                                    black_kings |= square;
-                                }
-                                'p' => {
-                                    black_pawns |= square;
-                                }
-                                'R' => {
-                                    white_rooks |= square;
-                                }
-                                'B' => {
-                                    white_bishops |= square;
-                                }
-                                'N' => {
-                                    white_knights |= square;
-                                }
-                                'Q' => {
-                                    white_queens |= square;
-                                }
-                                'K' => {
-                                    white_kings |= square;
-                                }
-                                'P' => {
-                                    white_pawns |= square;
-                                }
-                                _ => {
-                                    if let Some(empties) = char.to_digit(10) {
-                                        empty_cols += empties as usize - 1;
-                                    } else {
-                                        println!("{}", char);
-                                    }
-                                }
-                            }
-                        });
-                    });
-                }
-                1 => {
-                    if part == "w" {
-                        side_to_move = WHITE;
-                    } else {
-                        side_to_move = BLACK;
+        match Board::try_from_fen(fen) {
+            Ok(board) => board,
+            Err(error) => panic!("{}: {}", error, fen),
+        }
+    }
+
+    pub fn try_from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::WrongRankCount(ranks.len()));
+        }
+
+        // Indexed by color, then in the same order as #piece_bbs
+        let mut pieces = [[EMPTY; 6]; 2];
+        for (row_idx, row) in ranks.iter().enumerate() {
+            let rank = 7 - row_idx;
+            let mut file = 0;
+
+            for char in row.chars() {
+                if let Some(empties) = char.to_digit(10) {
+                    file += empties as usize;
+                    if empties == 0 || file > 8 {
+                        return Err(FenError::InvalidRankLength { rank: rank + 1 });
                     }
+                    continue;
                 }
-                2 => {
-                    castle_rights = part.chars().fold(castle_rights, |acc, piece| {
-                        acc | match piece.to_string().as_str() {
-                            "K" => G1_SQUARE,
-                            "Q" => C1_SQUARE,
-                            "k" => G8_SQUARE,
-                            "q" => C8_SQUARE,
-                            _ => EMPTY,
-                        }
-                    });
-                }
-                3 => {
-                    en_passant = match part.as_str() {
-                        "-" => EMPTY,
-                        _ => Board::square_from_notation(part),
-                    };
+
+                let piece = Piece::from_char(char).ok_or(FenError::UnknownPiece(char))?;
+
+                if file >= 8 {
+                    return Err(FenError::InvalidRankLength { rank: rank + 1 });
                 }
-                4 => {
-                    half_moves_since_action = part.parse::<u8>().unwrap();
+                pieces[piece.color.index()][piece.role.index()] |= SQUARES[rank * 8 + file];
+                file += 1;
+            }
+
+            if file != 8 {
+                return Err(FenError::InvalidRankLength { rank: rank + 1 });
+            }
+        }
+
+        for color in Color::ALL.iter() {
+            match pieces[color.index()][KINGS_BB].popcnt() {
+                0 => return Err(FenError::MissingKing(*color)),
+                1 => (),
+                _ => return Err(FenError::DuplicateKing(*color)),
+            }
+        }
+
+        let side_to_move = fields[1]
+            .parse::<Color>()
+            .map_err(|_| FenError::InvalidSideToMove(fields[1].to_string()))?;
+
+        let mut castle_rights = EMPTY;
+        if fields[2] != "-" {
+            for char in fields[2].chars() {
+                let right = match char {
+                    'K' => G1_SQUARE,
+                    'Q' => C1_SQUARE,
+                    'k' => G8_SQUARE,
+                    'q' => C8_SQUARE,
+                    _ => EMPTY,
+                };
+
+                if right.is_empty() || (castle_rights & right).is_not_empty() {
+                    return Err(FenError::InvalidCastleRights(fields[2].to_string()));
                 }
-                5 => {
-                    full_moves = part.parse::<u16>().unwrap();
+                castle_rights |= right;
+            }
+        }
+
+        // The en passant square is behind a pawn that has just moved two squares
+        let en_passant = match fields[3] {
+            "-" => EMPTY,
+            square => {
+                let mut chars = square.chars();
+                let expected_rank = if side_to_move == Color::White {
+                    '6'
+                } else {
+                    '3'
+                };
+                match (chars.next(), chars.next(), chars.next()) {
+                    (Some('a'..='h'), Some(rank), None) if rank == expected_rank => {
+                        Board::square_from_notation(square).bitboard()
+                    }
+                    _ => return Err(FenError::InvalidEnPassant(square.to_string())),
                 }
-                _ => {}
-            });
+            }
+        };
+
+        let (half_moves_since_action, full_moves) = if fields.len() == 6 {
+            (
+                fields[4]
+                    .parse::<u8>()
+                    .map_err(|_| FenError::InvalidHalfMoveClock(fields[4].to_string()))?,
+                fields[5]
+                    .parse::<u16>()
+                    .map_err(|_| FenError::InvalidFullMoveNumber(fields[5].to_string()))?,
+            )
+        } else {
+            (0, 1)
+        };
 
-        Board::new(BoardParams {
-            white_pawns,
-            white_knights,
-            white_bishops,
-            white_rooks,
-            white_queens,
-            white_kings,
-            black_pawns,
-            black_knights,
-            black_bishops,
-            black_rooks,
-            black_queens,
-            black_kings,
+        Ok(Board::new(BoardParams {
+            white_pawns: pieces[WHITE][PAWNS_BB],
+            white_knights: pieces[WHITE][KNIGHTS_BB],
+            white_bishops: pieces[WHITE][BISHOPS_BB],
+            white_rooks: pieces[WHITE][ROOKS_BB],
+            white_queens: pieces[WHITE][QUEENS_BB],
+            white_kings: pieces[WHITE][KINGS_BB],
+            black_pawns: pieces[BLACK][PAWNS_BB],
+            black_knights: pieces[BLACK][KNIGHTS_BB],
+            black_bishops: pieces[BLACK][BISHOPS_BB],
+            black_rooks: pieces[BLACK][ROOKS_BB],
+            black_queens: pieces[BLACK][QUEENS_BB],
+            black_kings: pieces[BLACK][KINGS_BB],
             side_to_move: Some(side_to_move),
             castle_rights: Some(castle_rights),
             en_passant: Some(en_passant),
             half_moves_since_action: Some(half_moves_since_action),
             full_moves: Some(full_moves),
-        })
+        }))
     }
 
     pub fn to_fen(&self) -> String {
@@ -352,12 +516,7 @@ impl Board {
             pieces.push(row_str.join(""));
         });
         let board_str = pieces.join("/");
-        let side_to_move_str: &str;
-        if self.side_to_move == WHITE {
-            side_to_move_str = "w";
-        } else {
-            side_to_move_str = "b";
-        }
+        let side_to_move_str = self.side_to_move.to_string();
         let mut castle_rights: Vec<&str> = Vec::new();
 
         if (self.castle_rights & G1_SQUARE).is_not_empty() {
@@ -376,13 +535,16 @@ impl Board {
             castle_rights.push("q");
         }
 
-        let castle_rights_str = castle_rights.join("");
-        let en_passant_str: String;
-        if self.en_passant.is_empty() {
-            en_passant_str = String::from("-");
+        let castle_rights_str = if castle_rights.is_empty() {
+            String::from("-")
         } else {
-            en_passant_str = Board::square_to_notation(self.en_passant);
-        }
+            castle_rights.join("")
+        };
+        let en_passant_str = self
+            .en_passant
+            .squares()
+            .next()
+            .map_or(String::from("-"), Board::square_to_notation);
 
         let half_moves_since_capture_promotion = self.half_moves_since_action.to_string();
         let full_moves = self.full_moves.to_string();
@@ -398,7 +560,17 @@ impl Board {
         )
     }
 
-    pub fn get_piece_at(&self, square: BitBoard) -> Pieces {
+    pub fn get_piece_at(&self, square: Square) -> Pieces {
+        self.mailbox[square.index()]
+    }
+
+    // Whether #mailbox agrees with the bitboards on every square
+    pub fn is_mailbox_consistent(&self) -> bool {
+        Square::all().all(|square| self.get_piece_at(square) == self.piece_from_bitboards(square))
+    }
+
+    fn piece_from_bitboards(&self, square: Square) -> Pieces {
+        let square = square.bitboard();
         if (self.combined_bbs[EMPTY_SQUARES_BB] & square).is_not_empty() {
             Pieces::Empty
         } else if (self.combined_bbs[ALL_PAWNS_BB] & square).is_not_empty() {
@@ -438,47 +610,16 @@ impl Board {
         }
     }
 
-    pub fn square_to_notation(square: BitBoard) -> String {
-        let (row, col) = (7 - square.row(), square.col());
-        let rank = (row + 1).to_string();
-        let file = match col {
-            0 => "a",
-            1 => "b",
-            2 => "c",
-            3 => "d",
-            4 => "e",
-            5 => "f",
-            6 => "g",
-            7 => "h",
-            _ => "",
-        };
-        format!("{}{}", file, rank)
-    }
-
-    pub fn square_from_notation(notation: &str) -> BitBoard {
-        let file: usize = match notation.chars().next().unwrap().to_string().as_str() {
-            "a" => 0,
-            "b" => 1,
-            "c" => 2,
-            "d" => 3,
-            "e" => 4,
-            "f" => 5,
-            "g" => 6,
-            "h" => 7,
-            _ => 0,
-        };
-
-        let rank = notation
-            .chars()
-            .nth(1)
-            .unwrap()
-            .to_string()
-            .parse::<usize>()
-            .unwrap()
-            - 1;
+    pub fn square_to_notation(square: Square) -> String {
+        square.to_string()
+    }
 
-        let square_pos = rank * 8 + file;
-        SQUARES[square_pos]
+    // Panics on invalid notation, parse a Square for a fallible version
+    pub fn square_from_notation(notation: &str) -> Square {
+        match notation.parse::<Square>() {
+            Ok(square) => square,
+            Err(error) => panic!("{}", error),
+        }
     }
 
     pub fn print_board(&self) {
@@ -486,64 +627,61 @@ impl Board {
     }
 
     pub fn current_pieces(&self) -> [BitBoard; 6] {
-        self.piece_bbs[self.side_to_move]
+        self.piece_bbs[self.side_to_move.index()]
     }
 
     pub fn other_pieces(&self) -> [BitBoard; 6] {
-        match self.side_to_move {
-            WHITE => self.piece_bbs[BLACK],
-            BLACK => self.piece_bbs[WHITE],
-            _ => panic!("Invalid side to move"),
-        }
+        self.piece_bbs[(!self.side_to_move).index()]
     }
 
     pub fn current_pieces_by_color(&self) -> BitBoard {
-        self.color_bbs[self.side_to_move]
+        self.color_bbs[self.side_to_move.index()]
     }
 
     pub fn other_pieces_by_color(&self) -> BitBoard {
-        match self.side_to_move {
-            WHITE => self.color_bbs[BLACK],
-            BLACK => self.color_bbs[WHITE],
-            _ => panic!("Invalid side to move"),
-        }
+        self.color_bbs[(!self.side_to_move).index()]
     }
 
     pub fn switch_side_to_move(&mut self) {
-        self.side_to_move ^= 1;
+        self.side_to_move = !self.side_to_move;
+        self.hash ^= Zobrist::side_to_move();
     }
 
-    pub fn get_material_eval_by_color(&self, color: usize) -> u32 {
-        let (pawns, bishops, knights, rooks, queens) = (
-            self.piece_bbs[color][PAWNS_BB].popcnt(),
-            self.piece_bbs[color][BISHOPS_BB].popcnt(),
-            self.piece_bbs[color][KNIGHTS_BB].popcnt(),
-            self.piece_bbs[color][ROOKS_BB].popcnt(),
-            self.piece_bbs[color][QUEENS_BB].popcnt(),
-        );
-
-        pawns * PAWN_VALUE
-            + bishops * BISHOP_VALUE
-            + knights * KNIGHT_VALUE
-            + rooks * ROOK_VALUE
-            + queens * QUEEN_VALUE
+    pub fn get_material_eval_by_color(&self, color: Color) -> u32 {
+        self.accumulators.material[color.index()].mg as u32
     }
 
     //TODO test
     pub fn get_material_eval(&self) -> (u32, u32) {
         (
-            self.get_material_eval_by_color(WHITE),
-            self.get_material_eval_by_color(BLACK),
+            self.get_material_eval_by_color(Color::White),
+            self.get_material_eval_by_color(Color::Black),
         )
     }
 }
 
+// #piece's share of #pawn_hash, which leaves out everything but pawns
+fn pawn_key(piece: Pieces, square: Square) -> u64 {
+    match piece {
+        Pieces::WPawn | Pieces::BPawn => Zobrist::piece(piece, square),
+        _ => 0,
+    }
+}
+
 impl Default for Board {
     fn default() -> Self {
         Board::new(BoardParams::default())
     }
 }
 
+impl FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Board::try_from_fen(fen)
+    }
+}
+
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -558,70 +696,70 @@ impl fmt::Display for Board {
             {}{}{}{}{}{}{}{}
             {}{}{}{}{}{}{}{}
         ",
-            self.get_piece_at(A8_SQUARE),
-            self.get_piece_at(B8_SQUARE),
-            self.get_piece_at(C8_SQUARE),
-            self.get_piece_at(D8_SQUARE),
-            self.get_piece_at(E8_SQUARE),
-            self.get_piece_at(F8_SQUARE),
-            self.get_piece_at(G8_SQUARE),
-            self.get_piece_at(H8_SQUARE),
-            self.get_piece_at(A7_SQUARE),
-            self.get_piece_at(B7_SQUARE),
-            self.get_piece_at(C7_SQUARE),
-            self.get_piece_at(D7_SQUARE),
-            self.get_piece_at(E7_SQUARE),
-            self.get_piece_at(F7_SQUARE),
-            self.get_piece_at(G7_SQUARE),
-            self.get_piece_at(H7_SQUARE),
-            self.get_piece_at(A6_SQUARE),
-            self.get_piece_at(B6_SQUARE),
-            self.get_piece_at(C6_SQUARE),
-            self.get_piece_at(D6_SQUARE),
-            self.get_piece_at(E6_SQUARE),
-            self.get_piece_at(F6_SQUARE),
-            self.get_piece_at(G6_SQUARE),
-            self.get_piece_at(H6_SQUARE),
-            self.get_piece_at(A5_SQUARE),
-            self.get_piece_at(B5_SQUARE),
-            self.get_piece_at(C5_SQUARE),
-            self.get_piece_at(D5_SQUARE),
-            self.get_piece_at(E5_SQUARE),
-            self.get_piece_at(F5_SQUARE),
-            self.get_piece_at(G5_SQUARE),
-            self.get_piece_at(H5_SQUARE),
-            self.get_piece_at(A4_SQUARE),
-            self.get_piece_at(B4_SQUARE),
-            self.get_piece_at(C4_SQUARE),
-            self.get_piece_at(D4_SQUARE),
-            self.get_piece_at(E4_SQUARE),
-            self.get_piece_at(F4_SQUARE),
-            self.get_piece_at(G4_SQUARE),
-            self.get_piece_at(H4_SQUARE),
-            self.get_piece_at(A3_SQUARE),
-            self.get_piece_at(B3_SQUARE),
-            self.get_piece_at(C3_SQUARE),
-            self.get_piece_at(D3_SQUARE),
-            self.get_piece_at(E3_SQUARE),
-            self.get_piece_at(F3_SQUARE),
-            self.get_piece_at(G3_SQUARE),
-            self.get_piece_at(H3_SQUARE),
-            self.get_piece_at(A2_SQUARE),
-            self.get_piece_at(B2_SQUARE),
-            self.get_piece_at(C2_SQUARE),
-            self.get_piece_at(D2_SQUARE),
-            self.get_piece_at(E2_SQUARE),
-            self.get_piece_at(F2_SQUARE),
-            self.get_piece_at(G2_SQUARE),
-            self.get_piece_at(H2_SQUARE),
-            self.get_piece_at(A1_SQUARE),
-            self.get_piece_at(B1_SQUARE),
-            self.get_piece_at(C1_SQUARE),
-            self.get_piece_at(D1_SQUARE),
-            self.get_piece_at(E1_SQUARE),
-            self.get_piece_at(F1_SQUARE),
-            self.get_piece_at(G1_SQUARE),
-            self.get_piece_at(H1_SQUARE),
+            self.get_piece_at(Square::A8),
+            self.get_piece_at(Square::B8),
+            self.get_piece_at(Square::C8),
+            self.get_piece_at(Square::D8),
+            self.get_piece_at(Square::E8),
+            self.get_piece_at(Square::F8),
+            self.get_piece_at(Square::G8),
+            self.get_piece_at(Square::H8),
+            self.get_piece_at(Square::A7),
+            self.get_piece_at(Square::B7),
+            self.get_piece_at(Square::C7),
+            self.get_piece_at(Square::D7),
+            self.get_piece_at(Square::E7),
+            self.get_piece_at(Square::F7),
+            self.get_piece_at(Square::G7),
+            self.get_piece_at(Square::H7),
+            self.get_piece_at(Square::A6),
+            self.get_piece_at(Square::B6),
+            self.get_piece_at(Square::C6),
+            self.get_piece_at(Square::D6),
+            self.get_piece_at(Square::E6),
+            self.get_piece_at(Square::F6),
+            self.get_piece_at(Square::G6),
+            self.get_piece_at(Square::H6),
+            self.get_piece_at(Square::A5),
+            self.get_piece_at(Square::B5),
+            self.get_piece_at(Square::C5),
+            self.get_piece_at(Square::D5),
+            self.get_piece_at(Square::E5),
+            self.get_piece_at(Square::F5),
+            self.get_piece_at(Square::G5),
+            self.get_piece_at(Square::H5),
+            self.get_piece_at(Square::A4),
+            self.get_piece_at(Square::B4),
+            self.get_piece_at(Square::C4),
+            self.get_piece_at(Square::D4),
+            self.get_piece_at(Square::E4),
+            self.get_piece_at(Square::F4),
+            self.get_piece_at(Square::G4),
+            self.get_piece_at(Square::H4),
+            self.get_piece_at(Square::A3),
+            self.get_piece_at(Square::B3),
+            self.get_piece_at(Square::C3),
+            self.get_piece_at(Square::D3),
+            self.get_piece_at(Square::E3),
+            self.get_piece_at(Square::F3),
+            self.get_piece_at(Square::G3),
+            self.get_piece_at(Square::H3),
+            self.get_piece_at(Square::A2),
+            self.get_piece_at(Square::B2),
+            self.get_piece_at(Square::C2),
+            self.get_piece_at(Square::D2),
+            self.get_piece_at(Square::E2),
+            self.get_piece_at(Square::F2),
+            self.get_piece_at(Square::G2),
+            self.get_piece_at(Square::H2),
+            self.get_piece_at(Square::A1),
+            self.get_piece_at(Square::B1),
+            self.get_piece_at(Square::C1),
+            self.get_piece_at(Square::D1),
+            self.get_piece_at(Square::E1),
+            self.get_piece_at(Square::F1),
+            self.get_piece_at(Square::G1),
+            self.get_piece_at(Square::H1),
         )
     }
 }
@@ -637,13 +775,141 @@ mod tests {
         #[test]
         fn it_works_with_initial_board() {
             let b = Board::default();
-            assert_eq!(b.get_piece_at(E2_SQUARE), Pieces::WPawn,);
+            assert_eq!(b.get_piece_at(Square::E2), Pieces::WPawn,);
 
-            assert_eq!(b.get_piece_at(E7_SQUARE), Pieces::BPawn,);
+            assert_eq!(b.get_piece_at(Square::E7), Pieces::BPawn,);
 
-            assert_eq!(b.get_piece_at(E8_SQUARE), Pieces::BKing,);
+            assert_eq!(b.get_piece_at(Square::E8), Pieces::BKing,);
 
-            assert_eq!(b.get_piece_at(E1_SQUARE), Pieces::WKing,);
+            assert_eq!(b.get_piece_at(Square::E1), Pieces::WKing,);
+        }
+    }
+
+    mod make_move {
+        use super::*;
+        use chessmove::ChessMove;
+
+        fn assert_round_trip(fen: &str, chessmove: ChessMove, expected_fen: &str) {
+            let mut b = Board::from_fen(fen);
+            let before = b;
+
+            let undo = b.make_move(&chessmove);
+            assert_eq!(b.to_fen(), expected_fen);
+            assert_eq!(b.hash, b.compute_hash());
+            assert_eq!(b.pawn_hash, b.compute_pawn_hash());
+            assert!(b.is_mailbox_consistent());
+
+            b.unmake_move(&chessmove, undo);
+            assert_eq!(b, before);
+        }
+
+        #[test]
+        fn it_handles_quiet_moves_and_captures() {
+            assert_round_trip(
+                "4k3/8/8/3p4/8/8/8/3RK3 w - - 20 40",
+                ChessMove::new(Square::D1, Square::D2),
+                "4k3/8/8/3p4/8/8/3R4/4K3 b - - 21 40",
+            );
+            assert_round_trip(
+                "4k3/8/8/3p4/8/8/8/3RK3 w - - 20 40",
+                ChessMove::new(Square::D1, Square::D5),
+                "4k3/8/8/3R4/8/8/8/4K3 b - - 0 40",
+            );
+        }
+
+        #[test]
+        fn it_handles_castling_and_castle_rights() {
+            assert_round_trip(
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 10",
+                ChessMove::new(Square::E1, Square::G1),
+                "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 4 10",
+            );
+            assert_round_trip(
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 10",
+                ChessMove::new(Square::E8, Square::C8),
+                "2kr3r/8/8/8/8/8/8/R3K2R w KQ - 4 11",
+            );
+            assert_round_trip(
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 10",
+                ChessMove::new(Square::A1, Square::A8),
+                "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 10",
+            );
+        }
+
+        #[test]
+        fn it_handles_en_passant() {
+            assert_round_trip(
+                "4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1",
+                ChessMove::new(Square::D7, Square::D5),
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+            );
+            assert_round_trip(
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+                ChessMove::new(Square::E5, Square::D6),
+                "4k3/8/3P4/8/8/8/8/4K3 b - - 0 2",
+            );
+        }
+
+        #[test]
+        fn it_handles_promotion() {
+            assert_round_trip(
+                "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                ChessMove::promote(Square::A7, Square::B8, Pieces::WQueen),
+                "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1",
+            );
+        }
+
+        #[test]
+        fn it_restores_the_derived_bitboards() {
+            let mut b = Board {
+                checkers: E4_SQUARE,
+                pinned: D4_SQUARE,
+                attacked_squares: RANK_3,
+                ..Board::default()
+            };
+            let before = b;
+
+            let chessmove = ChessMove::new(Square::G1, Square::F3);
+            let undo = b.make_move(&chessmove);
+            b.unmake_move(&chessmove, undo);
+
+            assert_eq!(b, before);
+        }
+    }
+
+    mod mailbox {
+        use super::*;
+
+        #[test]
+        fn it_matches_the_bitboards_after_setup() {
+            let b = Board::from_fen(
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            );
+            assert!(b.is_mailbox_consistent());
+            assert_eq!(b.mailbox[Square::F3.index()], Pieces::WQueen);
+            assert_eq!(b.mailbox[Square::A6.index()], Pieces::BBishop);
+            assert_eq!(b.mailbox[Square::D4.index()], Pieces::Empty);
+        }
+
+        #[test]
+        fn it_is_updated_by_every_change() {
+            let mut b = Board::from_fen("4k3/1P6/8/3p4/8/8/8/R3K2R w KQ - 0 1");
+            b.move_piece(Square::A1, Square::A8);
+            b.remove_piece(Square::D5);
+            b.promote_piece(Square::B7, Pieces::WKnight);
+
+            assert!(b.is_mailbox_consistent());
+            assert_eq!(b.get_piece_at(Square::A1), Pieces::Empty);
+            assert_eq!(b.get_piece_at(Square::A8), Pieces::WRook);
+            assert_eq!(b.get_piece_at(Square::D5), Pieces::Empty);
+            assert_eq!(b.get_piece_at(Square::B7), Pieces::WKnight);
+        }
+
+        #[test]
+        fn it_detects_diverging_bitboards() {
+            let mut b = Board::default();
+            b.mailbox[Square::E4.index()] = Pieces::WQueen;
+            assert!(!b.is_mailbox_consistent());
         }
     }
 
@@ -680,6 +946,189 @@ mod tests {
         }
     }
 
+    mod try_from_fen {
+        use super::*;
+
+        fn assert_error(fen: &str, error: FenError) {
+            assert_eq!(Board::try_from_fen(fen), Err(error), "{}", fen);
+        }
+
+        #[test]
+        fn it_works() {
+            let fen = "r3k2r/8/8/3pP3/8/8/8/R3K2R w Kq d6 3 20";
+            let b = Board::try_from_fen(fen).unwrap();
+
+            assert_eq!(b.to_fen(), fen);
+            assert_eq!(fen.parse::<Board>(), Ok(b));
+        }
+
+        #[test]
+        fn it_defaults_missing_clocks() {
+            let b = Board::try_from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+
+            assert_eq!(b.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        }
+
+        #[test]
+        fn it_rejects_malformed_fields() {
+            assert_error("4k3/8/8/8/8/8/8/4K3 w - - 0", FenError::WrongFieldCount(5));
+            assert_error("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::WrongRankCount(7));
+            assert_error(
+                "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+                FenError::InvalidRankLength { rank: 1 },
+            );
+            assert_error(
+                "4k3/9/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::InvalidRankLength { rank: 7 },
+            );
+            assert_error(
+                "4k3/8/8/8/8/8/8/4K2X w - - 0 1",
+                FenError::UnknownPiece('X'),
+            );
+            assert_error(
+                "4k3/8/8/8/8/8/8/4K3 W - - 0 1",
+                FenError::InvalidSideToMove(String::from("W")),
+            );
+            assert_error(
+                "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+                FenError::InvalidCastleRights(String::from("KK")),
+            );
+            assert_error(
+                "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+                FenError::InvalidEnPassant(String::from("e3")),
+            );
+            assert_error(
+                "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+                FenError::InvalidHalfMoveClock(String::from("x")),
+            );
+            assert_error(
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 -1",
+                FenError::InvalidFullMoveNumber(String::from("-1")),
+            );
+        }
+
+        #[test]
+        fn it_requires_one_king_per_side() {
+            assert_error(
+                "8/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::MissingKing(Color::Black),
+            );
+            assert_error(
+                "4k3/8/8/8/8/8/8/4KK2 w - - 0 1",
+                FenError::DuplicateKing(Color::White),
+            );
+        }
+    }
+
+    mod validate {
+        use super::*;
+
+        fn errors(fen: &str) -> Vec<PositionError> {
+            Board::from_fen(fen).validate().err().unwrap_or_default()
+        }
+
+        #[test]
+        fn it_accepts_legal_positions() {
+            assert_eq!(Board::default().validate(), Ok(()));
+            assert_eq!(
+                Board::from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").validate(),
+                Ok(())
+            );
+            assert_eq!(
+                Board::from_fen("4k3/8/8/8/8/8/8/4K2r w - - 0 1").validate(),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn it_rejects_pawns_on_the_back_ranks() {
+            assert_eq!(
+                errors("3Pk3/8/8/8/8/8/8/4K3 w - - 0 1"),
+                vec![PositionError::PawnsOnBackRank(D8_SQUARE)]
+            );
+        }
+
+        #[test]
+        fn it_rejects_the_side_not_to_move_in_check() {
+            assert_eq!(
+                errors("4k3/8/8/8/8/8/8/4K2r b - - 0 1"),
+                vec![PositionError::OpponentInCheck]
+            );
+            assert_eq!(
+                errors("4k3/8/8/8/8/8/3p4/4K3 b - - 0 1"),
+                vec![PositionError::OpponentInCheck]
+            );
+            assert_eq!(errors("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1"), vec![]);
+            assert_eq!(errors("4k3/8/8/8/8/8/4R3/4K3 b - - 0 1"), vec![]);
+            assert_eq!(errors("4k3/8/8/8/8/4n3/4R3/4K3 b - - 0 1"), vec![]);
+        }
+
+        #[test]
+        fn it_rejects_adjacent_kings() {
+            assert_eq!(
+                errors("8/8/8/8/8/8/4k3/4K3 w - - 0 1"),
+                vec![PositionError::AdjacentKings, PositionError::OpponentInCheck]
+            );
+        }
+
+        #[test]
+        fn it_rejects_impossible_material() {
+            assert_eq!(
+                errors("4k3/8/8/8/8/8/PPPPPPPP/QQQQK3 w - - 0 1"),
+                vec![PositionError::TooManyPromotedPieces(Color::White)]
+            );
+            let b = Board::new(BoardParams {
+                white_kings: EMPTY,
+                ..BoardParams::default()
+            });
+            assert_eq!(
+                b.validate(),
+                Err(vec![
+                    PositionError::MissingKing(Color::White),
+                    PositionError::InvalidCastleRights(G1_SQUARE | C1_SQUARE),
+                ])
+            );
+            assert_eq!(errors("4k3/8/8/8/8/8/8/4KQQQ w - - 0 1"), vec![]);
+
+            let mut b = Board::default();
+            b.piece_bbs[WHITE][PAWNS_BB] |= E4_SQUARE | D4_SQUARE;
+            b.piece_bbs[BLACK][KINGS_BB] |= A4_SQUARE;
+            b.color_bbs[WHITE] |= E4_SQUARE | D4_SQUARE;
+            b.color_bbs[BLACK] |= A4_SQUARE;
+            assert_eq!(
+                b.validate(),
+                Err(vec![
+                    PositionError::TooManyPieces(Color::White),
+                    PositionError::TooManyPawns(Color::White),
+                    PositionError::TooManyKings(Color::Black),
+                    PositionError::TooManyPieces(Color::Black),
+                ])
+            );
+
+            b.piece_bbs[BLACK][QUEENS_BB] |= E4_SQUARE;
+            assert_eq!(
+                b.validate().unwrap_err()[0],
+                PositionError::OverlappingPieces(E4_SQUARE)
+            );
+        }
+
+        #[test]
+        fn it_rejects_inconsistent_castle_rights_and_en_passant() {
+            assert_eq!(
+                errors("r3k2r/8/8/8/8/8/8/R4K1R w KQkq - 0 1"),
+                vec![PositionError::InvalidCastleRights(G1_SQUARE | C1_SQUARE)]
+            );
+            assert_eq!(
+                errors("4k3/8/8/8/8/8/8/4K3 w - d6 0 1"),
+                vec![PositionError::InvalidEnPassant(D6_SQUARE)]
+            );
+            assert_eq!(
+                errors("4k3/3p4/8/3p4/8/8/8/4K3 w - d6 0 1"),
+                vec![PositionError::InvalidEnPassant(D6_SQUARE)]
+            );
+        }
+    }
+
     mod to_fen {
         use super::*;
 
@@ -692,6 +1141,71 @@ mod tests {
                 "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
             );
         }
+
+        #[test]
+        fn it_writes_a_dash_without_castle_rights() {
+            let fen = "4k3/8/8/8/8/8/8/4K3 b - - 0 40";
+
+            assert_eq!(Board::from_fen(fen).to_fen(), fen);
+        }
+    }
+
+    mod hash {
+        use super::*;
+
+        #[test]
+        fn it_depends_on_every_part_of_the_position() {
+            // The pawn on d4 can take en passant on both e3 and c3, so the square is hashed
+            let fens = [
+                "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1",
+                "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+                "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b Qkq e3 0 1",
+                "rnbqkbnr/ppp1pppp/8/8/2Pp4/8/PP1PPPPP/RNBQKBNR b KQkq c3 0 1",
+            ];
+            let hashes: Vec<u64> = fens.iter().map(|fen| Board::from_fen(fen).hash).collect();
+
+            for (idx, hash) in hashes.iter().enumerate() {
+                assert!(!hashes[idx + 1..].contains(hash), "{}", fens[idx]);
+            }
+        }
+
+        #[test]
+        fn it_ignores_the_move_clocks() {
+            let b1 = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+            let b2 = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 12 40");
+
+            assert_eq!(b1.hash, b2.hash);
+        }
+
+        #[test]
+        fn it_is_updated_incrementally() {
+            let mut b = Board::from_fen("4k3/8/8/3p4/8/8/8/R3K2R w KQ - 0 1");
+            b.move_piece(Square::A1, Square::A8);
+            b.remove_piece(Square::D5);
+            b.remove_castle_rights(C1_SQUARE);
+            b.set_en_passant(E3_SQUARE);
+            b.switch_side_to_move();
+
+            assert_eq!(b.hash, b.compute_hash());
+            assert_eq!(
+                b.hash,
+                Board::from_fen("R3k3/8/8/8/8/8/8/4K2R b K e3 0 1").hash
+            );
+        }
+
+        #[test]
+        fn it_only_hashes_en_passant_squares_a_pawn_can_take_on() {
+            let after = |en_passant| {
+                Board::from_fen(&format!("4k3/8/8/8/3pP3/8/8/4K3 b - {} 0 1", en_passant)).hash
+            };
+            assert_ne!(after("e3"), after("-"));
+
+            let without_capturer = |en_passant| {
+                Board::from_fen(&format!("4k3/8/8/8/4P3/8/8/4K3 b - {} 0 1", en_passant)).hash
+            };
+            assert_eq!(without_capturer("e3"), without_capturer("-"));
+        }
     }
 
     mod square_to_notation {
@@ -699,12 +1213,12 @@ mod tests {
 
         #[test]
         fn it_works() {
-            assert_eq!("h8", Board::square_to_notation(H8_SQUARE));
-            assert_eq!("h2", Board::square_to_notation(H2_SQUARE));
-            assert_eq!("a1", Board::square_to_notation(A1_SQUARE));
-            assert_eq!("a8", Board::square_to_notation(A8_SQUARE));
-            assert_eq!("c5", Board::square_to_notation(C5_SQUARE));
-            assert_eq!("f3", Board::square_to_notation(F3_SQUARE));
+            assert_eq!("h8", Board::square_to_notation(Square::H8));
+            assert_eq!("h2", Board::square_to_notation(Square::H2));
+            assert_eq!("a1", Board::square_to_notation(Square::A1));
+            assert_eq!("a8", Board::square_to_notation(Square::A8));
+            assert_eq!("c5", Board::square_to_notation(Square::C5));
+            assert_eq!("f3", Board::square_to_notation(Square::F3));
         }
     }
 
@@ -713,12 +1227,12 @@ mod tests {
 
         #[test]
         fn it_works() {
-            assert_eq!(Board::square_from_notation("h8"), H8_SQUARE);
-            assert_eq!(Board::square_from_notation("h2"), H2_SQUARE);
-            assert_eq!(Board::square_from_notation("a1"), A1_SQUARE);
-            assert_eq!(Board::square_from_notation("a8"), A8_SQUARE);
-            assert_eq!(Board::square_from_notation("c5"), C5_SQUARE);
-            assert_eq!(Board::square_from_notation("f3"), F3_SQUARE);
+            assert_eq!(Board::square_from_notation("h8"), Square::H8);
+            assert_eq!(Board::square_from_notation("h2"), Square::H2);
+            assert_eq!(Board::square_from_notation("a1"), Square::A1);
+            assert_eq!(Board::square_from_notation("a8"), Square::A8);
+            assert_eq!(Board::square_from_notation("c5"), Square::C5);
+            assert_eq!(Board::square_from_notation("f3"), Square::F3);
         }
     }
 
@@ -728,7 +1242,7 @@ mod tests {
         #[test]
         fn it_evaluates_material_correctly() {
             let b =
-                Board::from_fen("b7/1PPP1pq1/1npn1pNP/R1P1p3/Pr5p/1pp3kB/P1R2N1p/3KB3 w  - 0 1");
+                Board::from_fen("b7/1PPP1pq1/1npn1pNP/R1P1p3/Pr5p/1pp3kB/P1R2N1p/3KB3 w - - 0 1");
             let adv = b.get_material_eval();
             assert_eq!(adv, (3000, 3150));
         }
@@ -754,4 +1268,142 @@ mod tests {
             assert_eq!(b.piece_bbs[BLACK][KINGS_BB], INITIAL_BLACK_KINGS);
         }
     }
+
+    mod see {
+        use super::*;
+        use chessmove::{ChessMove, Move};
+
+        fn see(fen: &str, from: &str, to: &str) -> i32 {
+            let b = Board::from_fen(fen);
+            b.see(b.encode_move(&ChessMove::from_notation(from, to)))
+        }
+
+        #[test]
+        fn it_wins_an_undefended_piece() {
+            let fen = "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1";
+            assert_eq!(see(fen, "e1", "e5"), PAWN_VALUE as i32);
+        }
+
+        #[test]
+        fn it_loses_the_capturing_piece_to_a_defender() {
+            let fen = "4k3/8/3p4/4p3/8/8/8/4RK2 w - - 0 1";
+            assert_eq!(see(fen, "e1", "e5"), PAWN_VALUE as i32 - ROOK_VALUE as i32);
+        }
+
+        #[test]
+        fn it_counts_attackers_behind_sliders() {
+            // The black queen on h8 backs up the bishop on f6, and the white queen on e1 the rook
+            let fen = "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1";
+            assert_eq!(
+                see(fen, "d3", "e5"),
+                PAWN_VALUE as i32 - KNIGHT_VALUE as i32
+            );
+        }
+
+        #[test]
+        fn it_stops_when_recapturing_loses_material() {
+            // Taking the rook back with the king would walk into the rook behind it
+            let fen = "8/8/4k3/3p4/8/8/3R4/3RK3 w - - 0 1";
+            assert_eq!(see(fen, "d2", "d5"), PAWN_VALUE as i32);
+
+            // Without it, the king wins the rook for the pawn
+            let fen = "8/8/4k3/3p4/8/8/3R4/4K3 w - - 0 1";
+            assert_eq!(see(fen, "d2", "d5"), PAWN_VALUE as i32 - ROOK_VALUE as i32);
+        }
+
+        #[test]
+        fn it_handles_en_passant_and_promotions() {
+            let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+            assert_eq!(see(fen, "e5", "d6"), PAWN_VALUE as i32);
+
+            let b = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+            let m = Move::new(Square::A7, Square::A8, Move::QUEEN_PROMOTION);
+            assert_eq!(b.see(m), QUEEN_VALUE as i32 - PAWN_VALUE as i32);
+        }
+
+        #[test]
+        fn it_compares_against_a_threshold() {
+            let b = Board::from_fen("4k3/8/3p4/4p3/8/8/8/4RK2 w - - 0 1");
+            let m = b.encode_move(&ChessMove::from_notation("e1", "e5"));
+
+            assert!(b.see_ge(m, -400));
+            assert!(!b.see_ge(m, 0));
+        }
+
+        #[test]
+        fn it_finds_attackers_of_both_colors() {
+            let b = Board::from_fen("4k3/8/3p4/4p3/8/5N2/8/4RK2 w - - 0 1");
+            let attackers = b.attackers_to(Square::E5, b.combined_bbs[ALL_PIECES_BB]);
+            assert_eq!(attackers, D6_SQUARE | F3_SQUARE | E1_SQUARE);
+
+            // A blocker on the file shuts the rook out unless it is left out of #occupied
+            let b = Board::from_fen("4k3/8/3p4/4p3/8/4PN2/8/4RK2 w - - 0 1");
+            let occupied = b.combined_bbs[ALL_PIECES_BB];
+            assert_eq!(b.attackers_to(Square::E5, occupied), D6_SQUARE | F3_SQUARE);
+            assert_eq!(
+                b.attackers_to(Square::E5, occupied & !E3_SQUARE),
+                D6_SQUARE | F3_SQUARE | E1_SQUARE
+            );
+        }
+    }
+
+    mod accumulators {
+        use super::*;
+        use chessmove::ChessMove;
+
+        #[test]
+        fn it_starts_from_the_pieces_on_the_board() {
+            let b = Board::default();
+
+            assert_eq!(b.accumulators, b.compute_accumulators());
+            assert_eq!(b.accumulators.phase, psqt::MAX_PHASE);
+            assert_eq!(
+                b.accumulators.material[WHITE],
+                b.accumulators.material[BLACK]
+            );
+            assert_eq!(
+                b.get_material_eval_by_color(Color::White),
+                8 * PAWN_VALUE + 2 * (KNIGHT_VALUE + BISHOP_VALUE + ROOK_VALUE) + QUEEN_VALUE
+            );
+        }
+
+        #[test]
+        fn it_follows_every_kind_of_move() {
+            let cases = [
+                // Capture, castling, en passant and a promotion that captures
+                ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "a1", "a8", None),
+                ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1", "g1", None),
+                ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5", "d6", None),
+                (
+                    "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                    "a7",
+                    "b8",
+                    Some(Pieces::WQueen),
+                ),
+            ];
+
+            for (fen, from, to, promotion) in cases.iter() {
+                let mut b = Board::from_fen(fen);
+                let before = b.accumulators;
+                let mut chessmove = ChessMove::from_notation(from, to);
+                chessmove.promotion = *promotion;
+
+                let undo = b.make_move(&chessmove);
+                assert_eq!(b.accumulators, b.compute_accumulators(), "{}", fen);
+                assert_ne!(b.accumulators, before);
+
+                b.unmake_move(&chessmove, undo);
+                assert_eq!(b.accumulators, before, "{}", fen);
+            }
+        }
+
+        #[test]
+        fn it_counts_promoted_pieces_towards_the_phase() {
+            let mut b = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+            assert_eq!(b.accumulators.phase, 0);
+
+            b.make_move(&ChessMove::promote(Square::A7, Square::A8, Pieces::WQueen));
+            assert_eq!(b.accumulators.phase, 4);
+        }
+    }
 }
@@ -0,0 +1,201 @@
+use crate::bitboard::*;
+use crate::chessmove::{ChessMove, Move};
+use crate::constants::*;
+use crate::piece::{Color, Piece, Pieces, Role};
+use crate::Board;
+
+// Everything #make_move changes that cannot be worked out again from the move itself
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UndoInfo {
+    // The square a captured piece was taken on differs from the destination for en passant
+    pub captured: Option<(Square, Pieces)>,
+    pub castle_rights: BitBoard,
+    pub en_passant: BitBoard,
+    pub half_moves_since_action: u8,
+    pub checkers: BitBoard,
+    pub pinned: BitBoard,
+    pub attacked_squares: BitBoard,
+    pub hash: u64,
+    pub pawn_hash: u64,
+}
+
+impl Board {
+    // Plays #chessmove, which must be legal. Checkers, pins and attacked squares come from move
+    // generation, which builds on this crate, so they are left for the caller to recalculate with
+    // MoveGen::update_derived_bitboards. #unmake_move restores them along with everything else
+    pub fn make_move(&mut self, chessmove: &ChessMove) -> UndoInfo {
+        let moving_piece = self.get_piece_at(chessmove.from);
+        let captured = self
+            .captured_square(moving_piece, chessmove)
+            .map(|square| (square, self.get_piece_at(square)));
+
+        let undo = UndoInfo {
+            captured,
+            castle_rights: self.castle_rights,
+            en_passant: self.en_passant,
+            half_moves_since_action: self.half_moves_since_action,
+            checkers: self.checkers,
+            pinned: self.pinned,
+            attacked_squares: self.attacked_squares,
+            hash: self.hash,
+            pawn_hash: self.pawn_hash,
+        };
+
+        self.set_en_passant(EMPTY);
+
+        if let Some((square, piece)) = captured {
+            if matches!(piece, Pieces::WKing | Pieces::BKing) {
+                panic!("King invalidly captured");
+            }
+            self.remove_piece(square);
+        }
+
+        self.move_piece(chessmove.from, chessmove.to);
+        if let Some((rook_from, rook_to)) = castling_rook_move(moving_piece, chessmove) {
+            self.move_piece(rook_from, rook_to);
+        }
+
+        let lost_castle_rights =
+            castle_rights_lost(chessmove.from) | castle_rights_lost(chessmove.to);
+        if (self.castle_rights & lost_castle_rights).is_not_empty() {
+            self.remove_castle_rights(lost_castle_rights);
+        }
+
+        let is_pawn = moving_piece.role() == Some(Role::Pawn);
+        if is_pawn && chessmove.from.offset(0, 2) == Some(chessmove.to) {
+            self.set_en_passant(chessmove.from.bitboard().north());
+        } else if is_pawn && chessmove.from.offset(0, -2) == Some(chessmove.to) {
+            self.set_en_passant(chessmove.from.bitboard().south());
+        }
+
+        if let Some(promotion) = chessmove.promotion {
+            self.promote_piece(chessmove.to, promotion);
+        }
+
+        if is_pawn || captured.is_some() {
+            self.half_moves_since_action = 0;
+        } else {
+            self.half_moves_since_action = self.half_moves_since_action.saturating_add(1);
+        }
+
+        if self.side_to_move == Color::Black {
+            self.full_moves += 1;
+        }
+
+        self.switch_side_to_move();
+        debug_assert_eq!(self.hash, self.compute_hash());
+        debug_assert_eq!(self.accumulators, self.compute_accumulators());
+        debug_assert!(self.is_mailbox_consistent());
+        debug_assert_eq!(self.pawn_hash, self.compute_pawn_hash());
+
+        undo
+    }
+
+    // Takes back #chessmove, which must be the last move made on this board, with the #undo
+    // #make_move returned for it
+    pub fn unmake_move(&mut self, chessmove: &ChessMove, undo: UndoInfo) {
+        self.side_to_move = !self.side_to_move;
+        if self.side_to_move == Color::Black {
+            self.full_moves -= 1;
+        }
+
+        if chessmove.promotion.is_some() {
+            let pawn = Pieces::new(Piece::new(self.side_to_move, Role::Pawn));
+            self.promote_piece(chessmove.to, pawn);
+        }
+
+        let moved_piece = self.get_piece_at(chessmove.to);
+        self.move_piece(chessmove.to, chessmove.from);
+        if let Some((rook_from, rook_to)) = castling_rook_move(moved_piece, chessmove) {
+            self.move_piece(rook_to, rook_from);
+        }
+
+        if let Some((square, piece)) = undo.captured {
+            self.put_piece(square, piece);
+        }
+
+        self.castle_rights = undo.castle_rights;
+        self.en_passant = undo.en_passant;
+        self.half_moves_since_action = undo.half_moves_since_action;
+        self.checkers = undo.checkers;
+        self.pinned = undo.pinned;
+        self.attacked_squares = undo.attacked_squares;
+        self.hash = undo.hash;
+        self.pawn_hash = undo.pawn_hash;
+        debug_assert_eq!(self.hash, self.compute_hash());
+        debug_assert_eq!(self.accumulators, self.compute_accumulators());
+        debug_assert!(self.is_mailbox_consistent());
+        debug_assert_eq!(self.pawn_hash, self.compute_pawn_hash());
+    }
+
+    // Packs #chessmove, which must be legal here, together with the kind of move it is
+    pub fn encode_move(&self, chessmove: &ChessMove) -> Move {
+        let moving_piece = self.get_piece_at(chessmove.from);
+        let is_capture = self.captured_square(moving_piece, chessmove).is_some();
+        let is_pawn = moving_piece.role() == Some(Role::Pawn);
+
+        let flags = if let Some(role) = chessmove.promotion.and_then(|piece| piece.role()) {
+            Move::promotion_flags(role, is_capture)
+        } else if is_pawn && is_capture && self.get_piece_at(chessmove.to) == Pieces::Empty {
+            Move::EN_PASSANT
+        } else if is_capture {
+            Move::CAPTURE
+        } else if let Some((rook_from, _)) = castling_rook_move(moving_piece, chessmove) {
+            if rook_from.file() == File::H {
+                Move::KING_CASTLE
+            } else {
+                Move::QUEEN_CASTLE
+            }
+        } else if is_pawn
+            && (chessmove.from.offset(0, 2) == Some(chessmove.to)
+                || chessmove.from.offset(0, -2) == Some(chessmove.to))
+        {
+            Move::DOUBLE_PUSH
+        } else {
+            Move::QUIET
+        };
+
+        Move::new(chessmove.from, chessmove.to, flags)
+    }
+
+    // Where the piece captured by #chessmove stands, if there is one
+    fn captured_square(&self, moving_piece: Pieces, chessmove: &ChessMove) -> Option<Square> {
+        if self.get_piece_at(chessmove.to) != Pieces::Empty {
+            return Some(chessmove.to);
+        }
+
+        match moving_piece {
+            Pieces::WPawn if chessmove.to.bitboard() == self.en_passant => {
+                chessmove.to.offset(0, -1)
+            }
+            Pieces::BPawn if chessmove.to.bitboard() == self.en_passant => {
+                chessmove.to.offset(0, 1)
+            }
+            _ => None,
+        }
+    }
+}
+
+// The rook's move when #piece castles with #chessmove
+pub fn castling_rook_move(piece: Pieces, chessmove: &ChessMove) -> Option<(Square, Square)> {
+    match (piece, chessmove.from, chessmove.to) {
+        (Pieces::WKing, Square::E1, Square::G1) => Some((Square::H1, Square::F1)),
+        (Pieces::WKing, Square::E1, Square::C1) => Some((Square::A1, Square::D1)),
+        (Pieces::BKing, Square::E8, Square::G8) => Some((Square::H8, Square::F8)),
+        (Pieces::BKing, Square::E8, Square::C8) => Some((Square::A8, Square::D8)),
+        _ => None,
+    }
+}
+
+// Moving a piece from or to a king or rook home square loses the castle rights depending on it
+fn castle_rights_lost(square: Square) -> BitBoard {
+    match square {
+        Square::A1 => C1_SQUARE,
+        Square::H1 => G1_SQUARE,
+        Square::E1 => C1_SQUARE | G1_SQUARE,
+        Square::A8 => C8_SQUARE,
+        Square::H8 => G8_SQUARE,
+        Square::E8 => C8_SQUARE | G8_SQUARE,
+        _ => EMPTY,
+    }
+}
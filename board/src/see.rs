@@ -0,0 +1,127 @@
+use crate::bitboard::*;
+use crate::chessmove::Move;
+use crate::constants::*;
+use crate::magic::tables::{KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS};
+use crate::magic::{bishop_attacks, rook_attacks};
+use crate::piece::{Color, Role};
+use crate::Board;
+
+// Indexed by Role. The king is worth more than anything it could win, so it only ever takes last
+const SEE_VALUES: [i32; 6] = [
+    PAWN_VALUE as i32,
+    KNIGHT_VALUE as i32,
+    BISHOP_VALUE as i32,
+    ROOK_VALUE as i32,
+    QUEEN_VALUE as i32,
+    QUEEN_VALUE as i32 * 10,
+];
+
+// Longer exchanges would need more than the 32 pieces on the board
+const MAX_EXCHANGE: usize = 32;
+
+impl Board {
+    // Material the side to move wins by playing #m, assuming both sides keep recapturing on its
+    // destination with their least valuable piece for as long as that does not lose them material.
+    // Sliders lined up behind a piece join in once it has captured. Follows the swap algorithm from
+    // https://www.chessprogramming.org/SEE_-_The_Swap_Algorithm
+    pub fn see(&self, m: Move) -> i32 {
+        let (from, to) = (m.from_square(), m.to_square());
+        let mut occupied = self.combined_bbs[ALL_PIECES_BB];
+        let mut attacker = self
+            .get_piece_at(from)
+            .role()
+            .expect("No piece to move in see");
+
+        let mut gain = [0; MAX_EXCHANGE];
+        gain[0] = if m.is_en_passant() {
+            // The captured pawn is behind the destination, and no longer blocks sliders
+            occupied &= !match self.side_to_move {
+                Color::White => to.bitboard().south(),
+                Color::Black => to.bitboard().north(),
+            };
+            SEE_VALUES[Role::Pawn.index()]
+        } else {
+            self.get_piece_at(to)
+                .role()
+                .map_or(0, |role| SEE_VALUES[role.index()])
+        };
+        if let Some(promotion) = m.promotion() {
+            gain[0] += SEE_VALUES[promotion.index()] - SEE_VALUES[Role::Pawn.index()];
+            attacker = promotion;
+        }
+
+        let mut attackers = self.attackers_to(to, occupied);
+        let mut from = from.bitboard();
+        let mut side = self.side_to_move;
+        let mut depth = 0;
+
+        loop {
+            depth += 1;
+            // What the other side wins by taking the piece that just captured, should it be able to
+            gain[depth] = SEE_VALUES[attacker.index()] - gain[depth - 1];
+            if (-gain[depth - 1]).max(gain[depth]) < 0 || depth + 1 == MAX_EXCHANGE {
+                break;
+            }
+
+            occupied &= !from;
+            attackers = (attackers | self.slider_attackers_to(to, occupied)) & occupied;
+            side = !side;
+
+            match self.least_valuable_attacker(attackers, side) {
+                Some((square, role)) => {
+                    from = square;
+                    attacker = role;
+                }
+                None => break,
+            }
+        }
+
+        // Each side stops capturing as soon as going on would lose it material
+        while depth > 1 {
+            depth -= 1;
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        }
+
+        gain[0]
+    }
+
+    // Whether #m wins at least #threshold, as worked out by #see
+    pub fn see_ge(&self, m: Move, threshold: i32) -> bool {
+        self.see(m) >= threshold
+    }
+
+    // The pieces of both colors attacking #square, with #occupied standing in for the pieces on
+    // the board when sliders look for blockers
+    pub fn attackers_to(&self, square: Square, occupied: BitBoard) -> BitBoard {
+        let idx = square.index();
+        let white = self.piece_bbs[WHITE];
+        let black = self.piece_bbs[BLACK];
+
+        (PAWN_ATTACKS[BLACK][idx] & white[PAWNS_BB])
+            | (PAWN_ATTACKS[WHITE][idx] & black[PAWNS_BB])
+            | (KNIGHT_ATTACKS[idx] & self.combined_bbs[ALL_KNIGHTS_BB])
+            | (KING_ATTACKS[idx] & self.combined_bbs[ALL_KINGS_BB])
+            | self.slider_attackers_to(square, occupied)
+    }
+
+    fn slider_attackers_to(&self, square: Square, occupied: BitBoard) -> BitBoard {
+        let queens = self.combined_bbs[ALL_QUEENS_BB];
+
+        (bishop_attacks(square.bitboard(), occupied) & (self.combined_bbs[ALL_BISHOPS_BB] | queens))
+            | (rook_attacks(square.bitboard(), occupied)
+                & (self.combined_bbs[ALL_ROOKS_BB] | queens))
+    }
+
+    fn least_valuable_attacker(
+        &self,
+        attackers: BitBoard,
+        color: Color,
+    ) -> Option<(BitBoard, Role)> {
+        let pieces = self.piece_bbs[color.index()];
+
+        Role::ALL.iter().find_map(|role| {
+            let candidates = attackers & pieces[role.index()];
+            candidates.lsb().map(|square| (square.bitboard(), *role))
+        })
+    }
+}
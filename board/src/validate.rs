@@ -0,0 +1,269 @@
+use crate::bitboard::*;
+use crate::constants::*;
+use crate::piece::Color;
+use crate::Board;
+
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionError {
+    // Holds the squares claimed by more than one piece
+    OverlappingPieces(BitBoard),
+    // Errors holding a color refer to the side at fault
+    MissingKing(Color),
+    TooManyKings(Color),
+    TooManyPieces(Color),
+    TooManyPawns(Color),
+    // More pieces than could have been promoted from the pawns no longer on the board
+    TooManyPromotedPieces(Color),
+    PawnsOnBackRank(BitBoard),
+    AdjacentKings,
+    OpponentInCheck,
+    // Holds the castle rights (G1, C1, G8 or C8) without their king and rook on their home squares
+    InvalidCastleRights(BitBoard),
+    InvalidEnPassant(BitBoard),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let color = |color: &Color| match color {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+
+        match self {
+            PositionError::OverlappingPieces(squares) => {
+                write!(f, "More than one piece on {} squares", squares.popcnt())
+            }
+            PositionError::MissingKing(side) => write!(f, "{} has no king", color(side)),
+            PositionError::TooManyKings(side) => {
+                write!(f, "{} has more than one king", color(side))
+            }
+            PositionError::TooManyPieces(side) => {
+                write!(f, "{} has more than 16 pieces", color(side))
+            }
+            PositionError::TooManyPawns(side) => write!(f, "{} has more than 8 pawns", color(side)),
+            PositionError::TooManyPromotedPieces(side) => {
+                write!(
+                    f,
+                    "{} has more promoted pieces than missing pawns",
+                    color(side)
+                )
+            }
+            PositionError::PawnsOnBackRank(_) => write!(f, "Pawns on the first or last rank"),
+            PositionError::AdjacentKings => write!(f, "The kings are next to each other"),
+            PositionError::OpponentInCheck => write!(f, "The side not to move is in check"),
+            PositionError::InvalidCastleRights(_) => {
+                write!(f, "Castle rights without king and rook on their squares")
+            }
+            PositionError::InvalidEnPassant(square) => match Square::try_from(*square) {
+                Ok(square) => write!(f, "No pawn can have just moved past {}", square),
+                Err(_) => write!(f, "More than one en passant square"),
+            },
+        }
+    }
+}
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+impl Board {
+    // Checks that the position could have come out of a legal game. Every problem found is returned,
+    // not only the first
+    pub fn validate(&self) -> Result<(), Vec<PositionError>> {
+        let mut errors = Vec::new();
+
+        let mut seen = EMPTY;
+        let mut overlapping = EMPTY;
+        for bb in self.piece_bbs.iter().flat_map(|bbs| bbs.iter()) {
+            overlapping |= seen & *bb;
+            seen |= *bb;
+        }
+        if overlapping.is_not_empty() {
+            errors.push(PositionError::OverlappingPieces(overlapping));
+        }
+
+        for color in Color::ALL.iter() {
+            errors.extend(self.validate_material(*color));
+        }
+
+        let pawns_on_back_rank = self.combined_bbs[ALL_PAWNS_BB] & (RANK_1 | RANK_8);
+        if pawns_on_back_rank.is_not_empty() {
+            errors.push(PositionError::PawnsOnBackRank(pawns_on_back_rank));
+        }
+
+        let white_king = self.piece_bbs[WHITE][KINGS_BB];
+        let black_king = self.piece_bbs[BLACK][KINGS_BB];
+        if white_king.popcnt() == 1 && black_king.popcnt() == 1 {
+            if is_attacked_from(white_king, &KING_OFFSETS, black_king) {
+                errors.push(PositionError::AdjacentKings);
+            }
+
+            let other_king = self.piece_bbs[(!self.side_to_move).index()][KINGS_BB];
+            if self.is_attacked_by(other_king, self.side_to_move) {
+                errors.push(PositionError::OpponentInCheck);
+            }
+        }
+
+        let invalid_castle_rights = [
+            (G1_SQUARE, Color::White, E1_SQUARE, H1_SQUARE),
+            (C1_SQUARE, Color::White, E1_SQUARE, A1_SQUARE),
+            (G8_SQUARE, Color::Black, E8_SQUARE, H8_SQUARE),
+            (C8_SQUARE, Color::Black, E8_SQUARE, A8_SQUARE),
+        ]
+        .iter()
+        .fold(EMPTY, |invalid, (right, color, king, rook)| {
+            let pieces = self.piece_bbs[color.index()];
+            let has_pieces = (pieces[KINGS_BB] & *king).is_not_empty()
+                && (pieces[ROOKS_BB] & *rook).is_not_empty();
+
+            if (self.castle_rights & *right).is_not_empty() && !has_pieces {
+                invalid | *right
+            } else {
+                invalid
+            }
+        });
+        if invalid_castle_rights.is_not_empty() {
+            errors.push(PositionError::InvalidCastleRights(invalid_castle_rights));
+        }
+
+        if self.en_passant.is_not_empty() && !self.is_valid_en_passant() {
+            errors.push(PositionError::InvalidEnPassant(self.en_passant));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_material(&self, color: Color) -> Vec<PositionError> {
+        let mut errors = Vec::new();
+        let pieces = self.piece_bbs[color.index()];
+
+        match pieces[KINGS_BB].popcnt() {
+            0 => errors.push(PositionError::MissingKing(color)),
+            1 => (),
+            _ => errors.push(PositionError::TooManyKings(color)),
+        }
+
+        if self.color_bbs[color.index()].popcnt() > 16 {
+            errors.push(PositionError::TooManyPieces(color));
+        }
+
+        let pawns = pieces[PAWNS_BB].popcnt();
+        if pawns > 8 {
+            errors.push(PositionError::TooManyPawns(color));
+        }
+
+        let promoted = [
+            (KNIGHTS_BB, 2),
+            (BISHOPS_BB, 2),
+            (ROOKS_BB, 2),
+            (QUEENS_BB, 1),
+        ]
+        .iter()
+        .map(|(piece, initial)| pieces[*piece].popcnt().saturating_sub(*initial))
+        .sum::<u32>();
+        if promoted > 8u32.saturating_sub(pawns) {
+            errors.push(PositionError::TooManyPromotedPieces(color));
+        }
+
+        errors
+    }
+
+    // The pawn that just moved two squares must be in front of the en passant square, with the
+    // square it came from and the en passant square itself both empty
+    fn is_valid_en_passant(&self) -> bool {
+        let (rank, pawn, origin) = if self.side_to_move == Color::White {
+            (RANK_6, self.en_passant.shr(8), self.en_passant.shl(8))
+        } else {
+            (RANK_3, self.en_passant.shl(8), self.en_passant.shr(8))
+        };
+
+        self.en_passant.popcnt() == 1
+            && (self.en_passant & rank).is_not_empty()
+            && (self.piece_bbs[(!self.side_to_move).index()][PAWNS_BB] & pawn).is_not_empty()
+            && (self.combined_bbs[ALL_PIECES_BB] & (self.en_passant | origin)).is_empty()
+    }
+
+    // Walks the board square by square rather than using the movegen tables, which depend on this crate
+    fn is_attacked_by(&self, square: BitBoard, color: Color) -> bool {
+        let pieces = self.piece_bbs[color.index()];
+        let pawn_direction = if color == Color::White { -1 } else { 1 };
+
+        is_attacked_from(square, &KNIGHT_OFFSETS, pieces[KNIGHTS_BB])
+            || is_attacked_from(square, &KING_OFFSETS, pieces[KINGS_BB])
+            || is_attacked_from(
+                square,
+                &[(pawn_direction, -1), (pawn_direction, 1)],
+                pieces[PAWNS_BB],
+            )
+            || KING_OFFSETS.iter().enumerate().any(|(idx, direction)| {
+                // Even offsets are straight lines, odd offsets diagonals
+                let sliders = if idx % 2 == 0 {
+                    pieces[ROOKS_BB] | pieces[QUEENS_BB]
+                } else {
+                    pieces[BISHOPS_BB] | pieces[QUEENS_BB]
+                };
+                let blocker =
+                    first_piece_along(square, *direction, self.combined_bbs[ALL_PIECES_BB]);
+
+                (blocker & sliders).is_not_empty()
+            })
+    }
+}
+
+// (rank, file) of a single square bitboard
+fn coords(square: BitBoard) -> (i8, i8) {
+    (square.index() as i8 / 8, square.index() as i8 % 8)
+}
+
+fn offset(square: BitBoard, (rank_offset, file_offset): (i8, i8)) -> Option<BitBoard> {
+    let (rank, file) = coords(square);
+    let (rank, file) = (rank + rank_offset, file + file_offset);
+
+    if (0..8).contains(&rank) && (0..8).contains(&file) {
+        Some(SQUARES[(rank * 8 + file) as usize])
+    } else {
+        None
+    }
+}
+
+fn is_attacked_from(square: BitBoard, offsets: &[(i8, i8)], attackers: BitBoard) -> bool {
+    offsets
+        .iter()
+        .any(|step| offset(square, *step).is_some_and(|from| (from & attackers).is_not_empty()))
+}
+
+fn first_piece_along(square: BitBoard, direction: (i8, i8), occupied: BitBoard) -> BitBoard {
+    let mut current = square;
+    while let Some(next) = offset(current, direction) {
+        if (next & occupied).is_not_empty() {
+            return next;
+        }
+        current = next;
+    }
+
+    EMPTY
+}
@@ -0,0 +1,73 @@
+use crate::bitboard::{BitBoard, Square};
+use crate::constants::*;
+use crate::piece::Pieces;
+
+const PIECE_KEYS: usize = 2 * 6 * 64;
+const CASTLE_KEYS: usize = 16;
+const EN_PASSANT_KEYS: usize = 8;
+const KEY_COUNT: usize = PIECE_KEYS + CASTLE_KEYS + EN_PASSANT_KEYS + 1;
+
+// Generated at compile time from a fixed seed, so hashes are the same on every run
+static KEYS: [u64; KEY_COUNT] = generate_keys(0x9E37_79B9_7F4A_7C15);
+
+// https://prng.di.unimi.it/splitmix64.c
+const fn generate_keys(seed: u64) -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state = seed;
+    let mut idx = 0;
+
+    while idx < KEY_COUNT {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[idx] = z ^ (z >> 31);
+        idx += 1;
+    }
+
+    keys
+}
+
+pub struct Zobrist;
+
+impl Zobrist {
+    pub fn piece(piece: Pieces, square: Square) -> u64 {
+        if piece == Pieces::Empty {
+            return 0;
+        }
+
+        let idx = (piece.color_bb_index() * 6 + piece.piece_by_color_bb_index()) * 64;
+        KEYS[idx + square.index()]
+    }
+
+    // #castle_rights is a set of the G1, C1, G8 and C8 squares, as stored on the board
+    pub fn castle_rights(castle_rights: BitBoard) -> u64 {
+        let idx = [G1_SQUARE, C1_SQUARE, G8_SQUARE, C8_SQUARE]
+            .iter()
+            .enumerate()
+            .fold(0, |idx, (bit, square)| {
+                if (castle_rights & *square).is_not_empty() {
+                    idx | 1 << bit
+                } else {
+                    idx
+                }
+            });
+
+        KEYS[PIECE_KEYS + idx]
+    }
+
+    // Only the file of the en passant square is hashed, and nothing at all when there is none. See
+    // Board#en_passant_key for when it is included
+    pub fn en_passant(en_passant: BitBoard) -> u64 {
+        if en_passant.is_empty() {
+            return 0;
+        }
+
+        KEYS[PIECE_KEYS + CASTLE_KEYS + en_passant.col()]
+    }
+
+    // Included whenever black is to move
+    pub fn side_to_move() -> u64 {
+        KEYS[KEY_COUNT - 1]
+    }
+}
@@ -1,20 +1,22 @@
 extern crate bitboard;
-use crate::bitboard::BitBoard;
-extern crate constants;
-use crate::constants::*;
+use crate::bitboard::Square;
 extern crate piece;
 use crate::piece::Pieces;
 
+mod move_list;
+pub use crate::move_list::{Move, MoveList, MAX_MOVES};
+
+use std::fmt;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ChessMove {
-    pub from: BitBoard,
-    pub to: BitBoard,
+    pub from: Square,
+    pub to: Square,
     pub promotion: Option<Pieces>,
 }
 
-//TODO handle promotion
 impl ChessMove {
-    pub fn new(from: BitBoard, to: BitBoard) -> Self {
+    pub fn new(from: Square, to: Square) -> Self {
         ChessMove {
             from,
             to,
@@ -22,7 +24,7 @@ impl ChessMove {
         }
     }
 
-    pub fn promote(from: BitBoard, to: BitBoard, promotion: Pieces) -> Self {
+    pub fn promote(from: Square, to: Square, promotion: Pieces) -> Self {
         ChessMove {
             from,
             to,
@@ -30,28 +32,24 @@ impl ChessMove {
         }
     }
 
-    //TODO test
+    // Accepts squares in either case, as in E2 or e2. Panics on invalid notation
     pub fn from_notation(from: &str, to: &str) -> Self {
-        //TODO implement
         ChessMove {
-            from: *NOTATION_MAP.get(from).unwrap(),
-            to: *NOTATION_MAP.get(to).unwrap(),
+            from: from.to_lowercase().parse().unwrap(),
+            to: to.to_lowercase().parse().unwrap(),
             promotion: None,
         }
     }
+}
 
-    // While #from will be a single square, #to represents every square available to the piece on #from
-    // #broken_up returns a new Vec where each ChessMove contains only a single destination square
-    pub fn broken_up(chessmoves: Vec<ChessMove>) -> Vec<ChessMove> {
-        let mut broken_up_chessmoves: Vec<ChessMove> = Vec::new();
-
-        for chessmove in chessmoves {
-            for bit in chessmove.to.bits() {
-                broken_up_chessmoves.push(ChessMove::new(chessmove.from, SQUARES[bit]))
-            }
+// Long algebraic notation as used by UCI, e.g. e2e4 or e7e8q
+impl fmt::Display for ChessMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion.and_then(|promotion| promotion.piece()) {
+            Some(piece) => write!(f, "{}", piece.role),
+            None => Ok(()),
         }
-
-        broken_up_chessmoves
     }
 }
 
@@ -65,10 +63,106 @@ mod tests {
         fn it_works() {
             let m = ChessMove::from_notation("E2", "E4");
 
-            assert_eq!(m.from, E2_SQUARE);
+            assert_eq!(m.from, Square::E2);
+            assert_eq!(m.to, Square::E4);
+        }
+    }
+
+    mod display {
+        use super::*;
+
+        #[test]
+        fn it_writes_long_algebraic_notation() {
+            assert_eq!(ChessMove::from_notation("E2", "E4").to_string(), "e2e4");
+            assert_eq!(
+                ChessMove::promote(Square::B2, Square::B1, Pieces::BKnight).to_string(),
+                "b2b1n"
+            );
+            assert_eq!(
+                Move::new(Square::A7, Square::A8, Move::QUEEN_PROMOTION).to_string(),
+                "a7a8q"
+            );
         }
     }
 
-    //TODO
-    mod broken_up {}
+    mod move_encoding {
+        use super::*;
+        use crate::piece::Role;
+
+        #[test]
+        fn it_round_trips_squares_and_flags() {
+            for flags in 0..16 {
+                let m = Move::new(Square::H7, Square::A2, flags);
+
+                assert_eq!(m.from_square(), Square::H7);
+                assert_eq!(m.to_square(), Square::A2);
+                assert_eq!(m.flags(), flags);
+                assert_eq!(Move::from_bits(m.bits()), m);
+            }
+        }
+
+        #[test]
+        fn it_classifies_moves_by_their_flags() {
+            let en_passant = Move::new(Square::E5, Square::D6, Move::EN_PASSANT);
+            assert!(en_passant.is_capture() && en_passant.is_en_passant());
+            assert!(!en_passant.is_promotion());
+
+            let castle = Move::new(Square::E1, Square::C1, Move::QUEEN_CASTLE);
+            assert!(castle.is_castle() && !castle.is_capture());
+
+            let double_push = Move::new(Square::E2, Square::E4, Move::DOUBLE_PUSH);
+            assert!(double_push.is_double_push() && !double_push.is_castle());
+
+            let flags = Move::promotion_flags(Role::Rook, true);
+            assert_eq!(flags, Move::ROOK_PROMOTION_CAPTURE);
+            let promotion = Move::new(Square::B7, Square::A8, flags);
+            assert!(promotion.is_capture());
+            assert_eq!(promotion.promotion(), Some(Role::Rook));
+        }
+
+        #[test]
+        fn it_converts_to_a_chessmove() {
+            let m = Move::new(Square::E2, Square::E4, Move::DOUBLE_PUSH);
+            assert_eq!(ChessMove::from(m), ChessMove::new(Square::E2, Square::E4));
+
+            let white = Move::new(Square::A7, Square::A8, Move::QUEEN_PROMOTION);
+            assert_eq!(
+                ChessMove::from(white),
+                ChessMove::promote(Square::A7, Square::A8, Pieces::WQueen)
+            );
+
+            let black = Move::new(Square::B2, Square::A1, Move::KNIGHT_PROMOTION_CAPTURE);
+            assert_eq!(
+                ChessMove::from(black),
+                ChessMove::promote(Square::B2, Square::A1, Pieces::BKnight)
+            );
+        }
+    }
+
+    mod move_list {
+        use super::*;
+
+        #[test]
+        fn it_holds_as_many_moves_as_any_position_has() {
+            let mut moves = MoveList::new();
+            for idx in 0..MAX_MOVES {
+                moves.push(Move::from_bits(idx as u16));
+            }
+
+            assert_eq!(moves.len(), MAX_MOVES);
+            assert_eq!(moves[MAX_MOVES - 1], Move::from_bits(MAX_MOVES as u16 - 1));
+        }
+
+        #[test]
+        fn it_retains_moves_in_order() {
+            let mut moves = MoveList::new();
+            for idx in 0..10 {
+                moves.push(Move::from_bits(idx));
+            }
+            moves.retain(|m| m.bits() % 3 == 0);
+
+            let bits: Vec<u16> = moves.iter().map(|m| m.bits()).collect();
+            assert_eq!(bits, vec![0, 3, 6, 9]);
+        }
+    }
 }
@@ -0,0 +1,201 @@
+use crate::bitboard::{Rank, Square};
+use crate::piece::{Color, Piece, Pieces, Role};
+use crate::ChessMove;
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+// No legal position has more moves than this
+pub const MAX_MOVES: usize = 256;
+
+// Pieces a pawn can promote to, indexed by the two lowest bits of a promotion's flags
+const PROMOTION_ROLES: [Role; 4] = [Role::Knight, Role::Bishop, Role::Rook, Role::Queen];
+
+// A move packed into 16 bits: the origin in bits 0-5, the destination in bits 6-11 and the kind
+// of move in bits 12-15, numbered as on https://www.chessprogramming.org/Encoding_Moves
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    pub const QUIET: u16 = 0;
+    pub const DOUBLE_PUSH: u16 = 1;
+    pub const KING_CASTLE: u16 = 2;
+    pub const QUEEN_CASTLE: u16 = 3;
+    pub const CAPTURE: u16 = 4;
+    pub const EN_PASSANT: u16 = 5;
+    // Promotions set bit 3, with the piece in the two lowest bits and bit 2 set for captures
+    pub const KNIGHT_PROMOTION: u16 = 8;
+    pub const BISHOP_PROMOTION: u16 = 9;
+    pub const ROOK_PROMOTION: u16 = 10;
+    pub const QUEEN_PROMOTION: u16 = 11;
+    pub const KNIGHT_PROMOTION_CAPTURE: u16 = 12;
+    pub const BISHOP_PROMOTION_CAPTURE: u16 = 13;
+    pub const ROOK_PROMOTION_CAPTURE: u16 = 14;
+    pub const QUEEN_PROMOTION_CAPTURE: u16 = 15;
+
+    // Panics if #flags does not fit in 4 bits
+    pub const fn new(from: Square, to: Square, flags: u16) -> Move {
+        assert!(flags < 16, "Move flags out of range");
+        Move(from.index() as u16 | (to.index() as u16) << 6 | flags << 12)
+    }
+
+    // Panics if #role is not a piece a pawn can promote to
+    pub fn promotion_flags(role: Role, is_capture: bool) -> u16 {
+        let piece = match role {
+            Role::Knight => 0,
+            Role::Bishop => 1,
+            Role::Rook => 2,
+            Role::Queen => 3,
+            Role::Pawn | Role::King => panic!("Cannot promote to {:?}", role),
+        };
+
+        Move::KNIGHT_PROMOTION | piece | if is_capture { Move::CAPTURE } else { 0 }
+    }
+
+    pub const fn from_bits(bits: u16) -> Move {
+        Move(bits)
+    }
+
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    pub const fn from_square(self) -> Square {
+        Square::new((self.0 & 0x3f) as u8)
+    }
+
+    pub const fn to_square(self) -> Square {
+        Square::new((self.0 >> 6 & 0x3f) as u8)
+    }
+
+    pub const fn flags(self) -> u16 {
+        self.0 >> 12
+    }
+
+    // En passant counts as a capture
+    pub const fn is_capture(self) -> bool {
+        self.flags() & Move::CAPTURE != 0
+    }
+
+    pub const fn is_double_push(self) -> bool {
+        self.flags() == Move::DOUBLE_PUSH
+    }
+
+    pub const fn is_en_passant(self) -> bool {
+        self.flags() == Move::EN_PASSANT
+    }
+
+    pub const fn is_castle(self) -> bool {
+        self.flags() == Move::KING_CASTLE || self.flags() == Move::QUEEN_CASTLE
+    }
+
+    pub const fn is_promotion(self) -> bool {
+        self.flags() & Move::KNIGHT_PROMOTION != 0
+    }
+
+    pub fn promotion(self) -> Option<Role> {
+        if self.is_promotion() {
+            Some(PROMOTION_ROLES[(self.flags() & 3) as usize])
+        } else {
+            None
+        }
+    }
+}
+
+// Only white promotes on the eighth rank, so the color of the promoted piece follows from the move
+impl From<Move> for ChessMove {
+    fn from(m: Move) -> ChessMove {
+        let (from, to) = (m.from_square(), m.to_square());
+        match m.promotion() {
+            Some(role) => {
+                let color = if to.rank() == Rank::Eighth {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                ChessMove::promote(from, to, Pieces::new(Piece::new(color, role)))
+            }
+            None => ChessMove::new(from, to),
+        }
+    }
+}
+
+// Written the same way as the ChessMove it stands for
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        ChessMove::from(*self).fmt(f)
+    }
+}
+
+// A fixed capacity list of moves that lives on the stack, so generating moves never allocates
+#[derive(Clone, Copy)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub const fn new() -> MoveList {
+        MoveList {
+            moves: [Move(0); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    // Panics once the list holds MAX_MOVES moves
+    pub fn push(&mut self, m: Move) {
+        self.moves[self.len] = m;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // Keeps only the moves #keep returns true for, in their original order
+    pub fn retain<F: FnMut(Move) -> bool>(&mut self, mut keep: F) {
+        let mut kept = 0;
+        for idx in 0..self.len {
+            if keep(self.moves[idx]) {
+                self.moves[kept] = self.moves[idx];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> MoveList {
+        MoveList::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
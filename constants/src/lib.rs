@@ -36,6 +36,9 @@ pub const INITIAL_BLACK_ROOKS: BitBoard = BitBoard(9295429630892703744);
 pub const INITIAL_BLACK_QUEENS: BitBoard = BitBoard(576460752303423488);
 pub const INITIAL_BLACK_KINGS: BitBoard = BitBoard(1152921504606846976);
 
+pub const LIGHT_SQUARES: BitBoard = BitBoard(0x55AA_55AA_55AA_55AA);
+pub const DARK_SQUARES: BitBoard = BitBoard(!LIGHT_SQUARES.0);
+
 pub const CLEAR_A_FILE: BitBoard = BitBoard(!A_FILE.0);
 pub const CLEAR_B_FILE: BitBoard = BitBoard(!B_FILE.0);
 pub const CLEAR_G_FILE: BitBoard = BitBoard(!G_FILE.0);
@@ -111,11 +114,16 @@ pub const WHITE_KINGSIDE_CASTLE_EMPTY_SQUARES: BitBoard = BitBoard(F1_SQUARE.0 |
 pub const WHITE_QUEENSIDE_CASTLE_EMPTY_SQUARES: BitBoard =
     BitBoard(D1_SQUARE.0 | C1_SQUARE.0 | B1_SQUARE.0);
 
+// The b-file square only needs to be empty, the king never crosses it
+pub const WHITE_QUEENSIDE_CASTLE_SAFE_SQUARES: BitBoard = BitBoard(D1_SQUARE.0 | C1_SQUARE.0);
+
 pub const BLACK_KINGSIDE_CASTLE_EMPTY_SQUARES: BitBoard = BitBoard(F8_SQUARE.0 | G8_SQUARE.0);
 
 pub const BLACK_QUEENSIDE_CASTLE_EMPTY_SQUARES: BitBoard =
     BitBoard(D8_SQUARE.0 | C8_SQUARE.0 | B8_SQUARE.0);
 
+pub const BLACK_QUEENSIDE_CASTLE_SAFE_SQUARES: BitBoard = BitBoard(D8_SQUARE.0 | C8_SQUARE.0);
+
 pub const INITIAL_CASTLE_RIGHTS: BitBoard =
     BitBoard(C1_SQUARE.0 | G1_SQUARE.0 | C8_SQUARE.0 | G8_SQUARE.0);
 
@@ -310,9 +318,9 @@ mod tests {
 
     #[test]
     fn empty_works() {
-        assert_eq!(EMPTY.is_empty(), true);
+        assert!(EMPTY.is_empty());
 
-        assert_eq!(A1_SQUARE.is_empty(), false);
+        assert!(!A1_SQUARE.is_empty());
     }
 
     #[test]
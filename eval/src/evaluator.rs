@@ -0,0 +1,123 @@
+use crate::board::Board;
+use crate::chessmove::ChessMove;
+use crate::constants::*;
+use crate::piece::Color;
+use crate::{evaluate_cached, phase, PawnHashTable, DEFAULT_PAWN_TABLE_ENTRIES};
+
+// Anything that can score a position for the search. Scores are centipawns from the perspective of
+// the side to move. The hooks let an evaluator keep its own incremental state in step with the
+// board, and do nothing by default
+pub trait Evaluator {
+    fn evaluate(&mut self, board: &Board) -> i32;
+
+    // Called with the board after #chessmove has been played on it
+    fn on_make_move(&mut self, _board: &Board, _chessmove: &ChessMove) {}
+
+    // Called with the board after #chessmove has been taken back
+    fn on_unmake_move(&mut self, _board: &Board, _chessmove: &ChessMove) {}
+
+    // Called before a new game, so nothing learned in the last one carries over
+    fn new_game(&mut self) {}
+}
+
+// Lets a Box<dyn Evaluator> be passed wherever an Evaluator is expected
+impl<E: Evaluator + ?Sized> Evaluator for Box<E> {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        (**self).evaluate(board)
+    }
+
+    fn on_make_move(&mut self, board: &Board, chessmove: &ChessMove) {
+        (**self).on_make_move(board, chessmove)
+    }
+
+    fn on_unmake_move(&mut self, board: &Board, chessmove: &ChessMove) {
+        (**self).on_unmake_move(board, chessmove)
+    }
+
+    fn new_game(&mut self) {
+        (**self).new_game()
+    }
+}
+
+// Counts material and nothing else
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Material;
+
+impl Evaluator for Material {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        let material = board.accumulators.material;
+        let score = (material[WHITE] - material[BLACK]).taper(phase(board));
+
+        match board.side_to_move {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+}
+
+// Every term of the evaluation, see Trace, with the pawn structure cached across positions
+#[derive(Clone)]
+pub struct Classical {
+    pawn_table: PawnHashTable,
+}
+
+impl Classical {
+    pub fn new(pawn_table_entries: usize) -> Classical {
+        Classical {
+            pawn_table: PawnHashTable::new(pawn_table_entries),
+        }
+    }
+
+    pub fn pawn_table(&self) -> &PawnHashTable {
+        &self.pawn_table
+    }
+}
+
+impl Default for Classical {
+    fn default() -> Classical {
+        Classical::new(DEFAULT_PAWN_TABLE_ENTRIES)
+    }
+}
+
+impl Evaluator for Classical {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        evaluate_cached(board, &mut self.pawn_table)
+    }
+
+    fn new_game(&mut self) {
+        self.pawn_table.clear();
+    }
+}
+
+// Scores every position with noise between -#amplitude and #amplitude, ignoring the position
+// entirely. The same seed always gives the same sequence of scores
+#[derive(Clone, Copy, Debug)]
+pub struct Random {
+    state: u64,
+    amplitude: i32,
+}
+
+impl Random {
+    pub fn new(seed: u64, amplitude: i32) -> Random {
+        Random {
+            state: seed,
+            amplitude: amplitude.abs(),
+        }
+    }
+
+    // SplitMix64, see https://prng.di.unimi.it/splitmix64.c
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl Evaluator for Random {
+    fn evaluate(&mut self, _board: &Board) -> i32 {
+        let range = 2 * self.amplitude as u64 + 1;
+        (self.next() % range) as i32 - self.amplitude
+    }
+}
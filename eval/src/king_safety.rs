@@ -0,0 +1,107 @@
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::constants::*;
+use crate::mobility::attacks;
+use crate::piece::{Color, Role};
+use crate::{PawnStructure, Score};
+
+// Indexed by Role. How much each piece attacking the squares around the king adds to the danger
+const ATTACK_WEIGHTS: [i32; 6] = [0, 20, 20, 40, 80, 0];
+// The percentage of the summed weights that counts, indexed by the number of attackers. A lone
+// attacker rarely gets anywhere, so it does not count at all
+const ATTACKER_SCALE: [i32; 8] = [0, 0, 50, 75, 88, 94, 97, 99];
+
+// Indexed by how many ranks in front of the king a pawn stands
+const SHIELD: [Score; 3] = [Score::ZERO, Score::new(15, 0), Score::new(8, 0)];
+const STORM: [Score; 4] = [
+    Score::ZERO,
+    Score::new(-5, 0),
+    Score::new(-20, 0),
+    Score::new(-10, 0),
+];
+
+// Files with no pawns at all, and files with only enemy pawns, on or next to the king's file
+const OPEN_FILE: Score = Score::new(-25, 0);
+const HALF_OPEN_FILE: Score = Score::new(-12, 0);
+
+// How safe the king of one color is, term by term, from that side's own point of view
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct KingSafety {
+    // Enemy knights, bishops, rooks and queens attacking the king or the squares next to it
+    pub attackers: u32,
+    pub attack: Score,
+    // Own pawns up to two ranks in front of the king, on its file or the files next to it
+    pub shield: Score,
+    // Enemy pawns up to three ranks in front of the king, on the same files
+    pub storm: Score,
+    pub files: Score,
+}
+
+impl KingSafety {
+    pub fn new(board: &Board, color: Color, pawns: &PawnStructure) -> KingSafety {
+        let king = board.piece_bbs[color.index()][KINGS_BB];
+        let ksq = match king.lsb() {
+            Some(ksq) => ksq,
+            None => return KingSafety::default(),
+        };
+        let king_files = (king | king.east() | king.west()).file_fill();
+
+        // Black is looked at on a flipped board, so the king always faces north
+        let flip = |bb: BitBoard| match color {
+            Color::White => bb,
+            Color::Black => bb.flip_vertical(),
+        };
+        let own_pawns = flip(board.piece_bbs[color.index()][PAWNS_BB]) & king_files;
+        let enemy_pawns = flip(board.piece_bbs[(!color).index()][PAWNS_BB]) & king_files;
+
+        let mut shield = Score::ZERO;
+        let mut storm = Score::ZERO;
+        let mut ahead = flip(king).rank_fill();
+        for distance in 1..STORM.len() {
+            ahead = ahead.north();
+            if distance < SHIELD.len() {
+                shield += SHIELD[distance] * (own_pawns & ahead).popcnt() as i32;
+            }
+            storm += STORM[distance] * (enemy_pawns & ahead).popcnt() as i32;
+        }
+
+        let (attackers, attack) = KingSafety::attack(board, color, ksq);
+        let files = OPEN_FILE * (pawns.open_files & king_files & RANK_1).popcnt() as i32
+            + HALF_OPEN_FILE
+                * (pawns.half_open_files[color.index()] & king_files & RANK_1).popcnt() as i32;
+
+        KingSafety {
+            attackers,
+            attack,
+            shield,
+            storm,
+            files,
+        }
+    }
+
+    pub fn score(&self) -> Score {
+        self.attack + self.shield + self.storm + self.files
+    }
+
+    fn attack(board: &Board, color: Color, ksq: Square) -> (u32, Score) {
+        let zone = attacks(color, Role::King, ksq, EMPTY) | ksq.bitboard();
+        let occupied = board.combined_bbs[ALL_PIECES_BB];
+        let enemy = !color;
+
+        let (attackers, weight) = [Role::Knight, Role::Bishop, Role::Rook, Role::Queen]
+            .iter()
+            .flat_map(|role| {
+                let pieces = board.piece_bbs[enemy.index()][role.index()];
+                pieces.squares().map(move |square| (*role, square))
+            })
+            .filter(|(role, square)| {
+                (attacks(enemy, *role, *square, occupied) & zone).is_not_empty()
+            })
+            .fold((0, 0), |(attackers, weight), (role, _)| {
+                (attackers + 1, weight + ATTACK_WEIGHTS[role.index()])
+            });
+
+        let scale = ATTACKER_SCALE[(attackers as usize).min(ATTACKER_SCALE.len() - 1)];
+        (attackers, Score::new(-weight * scale / 100, 0))
+    }
+}
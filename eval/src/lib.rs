@@ -0,0 +1,428 @@
+extern crate bitboard;
+extern crate board;
+use crate::board::Board;
+extern crate chessmove;
+extern crate constants;
+extern crate magic;
+extern crate piece;
+extern crate psqt;
+pub use crate::psqt::{psqt, Score, MATERIAL, MAX_PHASE, PHASE_WEIGHTS};
+
+mod evaluator;
+mod king_safety;
+mod mobility;
+mod pawns;
+mod trace;
+pub use evaluator::{Classical, Evaluator, Material, Random};
+pub use king_safety::KingSafety;
+pub use mobility::mobility;
+pub use pawns::{pawn_key, PawnHashTable, PawnStructure, DEFAULT_PAWN_TABLE_ENTRIES};
+pub use trace::Trace;
+
+// Promotions can take the count past MAX_PHASE, which still plays as a middlegame
+pub fn phase(board: &Board) -> i32 {
+    board.accumulators.phase.min(MAX_PHASE)
+}
+
+// Material and piece placement of both sides, from white's point of view. Read from the sums Board
+// keeps up to date as pieces move, see Board#compute_accumulators
+pub fn material_and_psqt(board: &Board) -> Score {
+    board.accumulators.score()
+}
+
+// Centipawns from the perspective of the side to move, blending the middlegame and endgame
+// scores by how much material is left
+pub fn evaluate(board: &Board) -> i32 {
+    score(board, &PawnStructure::from_board(board))
+}
+
+// Same as #evaluate, with the pawn structure looked up in #pawn_table rather than worked out again
+pub fn evaluate_cached(board: &Board, pawn_table: &mut PawnHashTable) -> i32 {
+    score(board, &pawn_table.probe(board))
+}
+
+// Every term that goes into #evaluate, reported separately
+pub fn trace(board: &Board) -> Trace {
+    Trace::new(board, &PawnStructure::from_board(board))
+}
+
+fn score(board: &Board, pawns: &PawnStructure) -> i32 {
+    Trace::new(board, pawns).evaluation()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The same position with the colors swapped and the board turned around, so white's pieces
+    // stand where black's did
+    fn mirror_fen(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let swap_case = |s: &str| -> String {
+            s.chars()
+                .map(|c| {
+                    if c.is_ascii_uppercase() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        c.to_ascii_uppercase()
+                    }
+                })
+                .collect()
+        };
+
+        let placement: Vec<String> = fields[0].split('/').rev().map(swap_case).collect();
+        let side = if fields[1] == "w" { "b" } else { "w" };
+        let castling: String = {
+            let mut rights: Vec<char> = swap_case(fields[2]).chars().collect();
+            rights.sort_by_key(|c| (c.is_ascii_lowercase(), *c != 'K' && *c != 'k'));
+            rights.into_iter().collect()
+        };
+        let en_passant = match fields[3] {
+            "-" => "-".to_string(),
+            square => square
+                .chars()
+                .map(|c| match c {
+                    '3' => '6',
+                    '6' => '3',
+                    c => c,
+                })
+                .collect(),
+        };
+
+        format!(
+            "{} {} {} {} {}",
+            placement.join("/"),
+            side,
+            castling,
+            en_passant,
+            fields[4..].join(" ")
+        )
+    }
+
+    mod phase {
+        use super::*;
+
+        #[test]
+        fn it_counts_down_from_the_starting_position() {
+            assert_eq!(phase(&Board::default()), MAX_PHASE);
+            assert_eq!(
+                phase(&Board::from_fen("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1")),
+                0
+            );
+            assert_eq!(
+                phase(&Board::from_fen("3qk3/8/8/8/8/8/8/2R1K3 w - - 0 1")),
+                6
+            );
+        }
+
+        #[test]
+        fn it_caps_the_phase_after_promotions() {
+            let b = Board::from_fen("qqqqk3/8/8/8/8/8/8/QQQQK3 w - - 0 1");
+            assert_eq!(phase(&b), MAX_PHASE);
+        }
+    }
+
+    mod evaluate {
+        use super::*;
+
+        pub const POSITIONS: [&str; 5] = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3pPk/8/4P3/8 b - g3 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1",
+        ];
+
+        #[test]
+        fn it_scores_the_starting_position_as_even() {
+            assert_eq!(evaluate(&Board::default()), 0);
+        }
+
+        #[test]
+        fn it_scores_mirrored_positions_the_same() {
+            for fen in POSITIONS.iter() {
+                let mirrored = mirror_fen(fen);
+                let (b, m) = (Board::from_fen(fen), Board::from_fen(&mirrored));
+
+                assert_eq!(evaluate(&b), evaluate(&m), "{} and {}", fen, mirrored);
+                assert_eq!(material_and_psqt(&b), -material_and_psqt(&m));
+            }
+        }
+
+        #[test]
+        fn it_scores_from_the_side_to_move() {
+            let white = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+            let black = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1");
+
+            assert!(evaluate(&white) > 0);
+            assert_eq!(evaluate(&white), -evaluate(&black));
+        }
+    }
+
+    mod pawns {
+        use super::*;
+        use crate::bitboard::*;
+        use crate::constants::*;
+
+        fn structure(fen: &str) -> PawnStructure {
+            PawnStructure::from_board(&Board::from_fen(fen))
+        }
+
+        #[test]
+        fn it_finds_passed_pawns() {
+            assert_eq!(
+                structure("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1").passed[WHITE],
+                D5_SQUARE
+            );
+            assert_eq!(
+                structure("4k3/8/2p5/3P4/8/8/8/4K3 w - - 0 1").passed[WHITE],
+                EMPTY
+            );
+            assert_eq!(
+                structure("4k3/3p4/8/3P4/8/8/8/4K3 w - - 0 1").passed[WHITE],
+                EMPTY
+            );
+            // A pawn level with it on the next file can no longer stop it
+            let s = structure("4k3/8/8/2pP4/8/8/8/4K3 w - - 0 1");
+            assert_eq!(s.passed, [D5_SQUARE, C5_SQUARE]);
+        }
+
+        #[test]
+        fn it_finds_isolated_and_doubled_pawns() {
+            let s = structure("4k3/8/8/8/8/2P5/P1P5/4K3 w - - 0 1");
+
+            assert_eq!(s.isolated[WHITE], A2_SQUARE | C2_SQUARE | C3_SQUARE);
+            assert_eq!(s.doubled[WHITE], C3_SQUARE);
+            assert_eq!(s.islands[WHITE], 2);
+        }
+
+        #[test]
+        fn it_finds_backward_pawns() {
+            // d3 can no longer be covered by the pawn on c4, and black's e5 pawn guards d4
+            let s = structure("4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1");
+            assert_eq!(s.backward[WHITE], D3_SQUARE);
+        }
+
+        #[test]
+        fn it_finds_connected_pawns() {
+            assert_eq!(
+                structure("4k3/8/8/8/3PP3/8/8/4K3 w - - 0 1").connected[WHITE],
+                D4_SQUARE | E4_SQUARE
+            );
+            assert_eq!(
+                structure("4k3/8/8/8/3P4/4P3/8/4K3 w - - 0 1").connected[WHITE],
+                D4_SQUARE
+            );
+        }
+
+        #[test]
+        fn it_finds_candidate_passed_pawns() {
+            // The b4 pawn can help a4 past the single pawn on b6
+            let s = structure("4k3/8/1p6/8/PP6/8/8/4K3 w - - 0 1");
+            assert_eq!(s.candidates[WHITE], A4_SQUARE);
+        }
+
+        #[test]
+        fn it_finds_open_and_half_open_files() {
+            let s = structure("4k3/4p3/8/8/8/8/3P4/4K3 w - - 0 1");
+
+            assert_eq!(s.open_files, !(D_FILE | E_FILE));
+            assert_eq!(s.half_open_files[WHITE], E_FILE);
+            assert_eq!(s.half_open_files[BLACK], D_FILE);
+        }
+
+        #[test]
+        fn it_treats_both_colors_the_same() {
+            let fen = "4k3/pp3p1p/2p1p3/3pP3/P2P4/2P5/1P3PPP/4K3 w - - 0 1";
+            let s = structure(fen);
+            let m = structure(&mirror_fen(fen));
+            let flip = |bbs: [BitBoard; 2]| [bbs[1].flip_vertical(), bbs[0].flip_vertical()];
+
+            assert_eq!(m.passed, flip(s.passed));
+            assert_eq!(m.candidates, flip(s.candidates));
+            assert_eq!(m.isolated, flip(s.isolated));
+            assert_eq!(m.doubled, flip(s.doubled));
+            assert_eq!(m.backward, flip(s.backward));
+            assert_eq!(m.connected, flip(s.connected));
+            assert_eq!(m.islands, [s.islands[1], s.islands[0]]);
+            assert_eq!(m.scores, [s.scores[1], s.scores[0]]);
+            assert_eq!(m.score(), -s.score());
+        }
+
+        #[test]
+        fn it_caches_structures_by_pawn_key() {
+            let b = Board::from_fen("4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1");
+            let other_pieces = Board::from_fen("r3k3/pp6/8/8/8/8/PP6/4K2R w - - 0 1");
+            assert_eq!(pawn_key(&b), pawn_key(&other_pieces));
+
+            let mut table = PawnHashTable::new(16);
+            assert_eq!(table.probe(&b), PawnStructure::from_board(&b));
+            assert_eq!(table.probe(&other_pieces), PawnStructure::from_board(&b));
+            assert_eq!(table.stats(), (1, 1));
+
+            table.clear();
+            table.probe(&b);
+            assert_eq!(table.stats(), (0, 1));
+            assert_eq!(evaluate_cached(&b, &mut table), evaluate(&b));
+        }
+    }
+
+    mod mobility {
+        use super::*;
+        use crate::piece::Color;
+
+        #[test]
+        fn it_scores_the_starting_position_the_same_for_both_sides() {
+            let b = Board::default();
+            assert_eq!(mobility(&b, Color::White), mobility(&b, Color::Black));
+        }
+
+        #[test]
+        fn it_prefers_centralized_pieces() {
+            let center = Board::from_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+            let corner = Board::from_fen("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+
+            assert!(mobility(&center, Color::White).mg > mobility(&corner, Color::White).mg);
+        }
+
+        #[test]
+        fn it_only_counts_squares_safe_from_enemy_pawns() {
+            let free = Board::from_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+            // The pawn on d7 covers c6 and e6, both of which the knight attacks
+            let covered = Board::from_fen("4k3/3p4/8/8/3N4/8/8/4K3 w - - 0 1");
+
+            assert_eq!(
+                mobility(&free, Color::White) - mobility(&covered, Color::White),
+                Score::new(8, 8)
+            );
+        }
+    }
+
+    mod king_safety {
+        use super::*;
+        use crate::piece::Color;
+
+        fn king_safety(fen: &str, color: Color) -> KingSafety {
+            let b = Board::from_fen(fen);
+            KingSafety::new(&b, color, &PawnStructure::from_board(&b))
+        }
+
+        #[test]
+        fn it_rewards_a_pawn_shield() {
+            let castled = king_safety("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1", Color::White);
+            let advanced = king_safety("4k3/8/8/8/5PPP/8/8/6K1 w - - 0 1", Color::White);
+
+            assert_eq!(castled.shield, Score::new(45, 0));
+            assert_eq!(advanced.shield, Score::ZERO);
+        }
+
+        #[test]
+        fn it_penalizes_a_pawn_storm() {
+            let storm = king_safety("6k1/5ppp/8/6P1/8/8/8/4K3 b - - 0 1", Color::Black);
+            assert_eq!(storm.storm, Score::new(-10, 0));
+        }
+
+        #[test]
+        fn it_penalizes_open_files_next_to_the_king() {
+            let open = king_safety("4k3/8/8/8/8/8/5P1P/6K1 w - - 0 1", Color::White);
+            let half_open = king_safety("4k3/6p1/8/8/8/8/5P1P/6K1 w - - 0 1", Color::White);
+
+            assert_eq!(open.files, Score::new(-25, 0));
+            assert_eq!(half_open.files, Score::new(-12, 0));
+        }
+
+        #[test]
+        fn it_weights_attackers_on_the_king_zone() {
+            let lone_knight = king_safety("4k3/8/8/8/8/5n2/5PPP/6K1 w - - 0 1", Color::White);
+            let queen_and_rook = king_safety("4k3/8/8/8/8/7q/5PPP/4r1K1 w - - 0 1", Color::White);
+
+            assert_eq!(lone_knight.attackers, 1);
+            assert_eq!(lone_knight.attack, Score::ZERO);
+            assert_eq!(queen_and_rook.attackers, 2);
+            assert_eq!(queen_and_rook.attack, Score::new(-60, 0));
+        }
+    }
+
+    mod trace {
+        use super::*;
+        use crate::constants::*;
+
+        #[test]
+        fn it_adds_up_to_the_evaluation() {
+            for fen in evaluate::POSITIONS.iter() {
+                let b = Board::from_fen(fen);
+                assert_eq!(trace(&b).evaluation(), evaluate(&b), "{}", fen);
+            }
+        }
+
+        #[test]
+        fn it_reports_both_colors_separately() {
+            let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+            let (t, m) = (
+                trace(&Board::from_fen(fen)),
+                trace(&Board::from_fen(&mirror_fen(fen))),
+            );
+
+            for ((name, term), (_, mirrored)) in t.terms().iter().zip(m.terms().iter()) {
+                assert_eq!(term[WHITE], mirrored[BLACK], "{}", name);
+                assert_eq!(term[BLACK], mirrored[WHITE], "{}", name);
+            }
+        }
+
+        #[test]
+        fn it_prints_every_term() {
+            let t = trace(&Board::default());
+            let printed = t.to_string();
+
+            for (name, _) in t.terms().iter() {
+                assert!(printed.contains(name), "{} missing from {}", name, printed);
+            }
+        }
+    }
+
+    mod evaluator {
+        use super::*;
+
+        #[test]
+        fn it_counts_only_material() {
+            let mut material = Material;
+            let b = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+            let centralized = Board::from_fen("4k3/8/8/8/3Q4/8/8/4K3 b - - 0 1");
+
+            assert_eq!(material.evaluate(&Board::default()), 0);
+            assert!(material.evaluate(&b) < 0);
+            assert_eq!(material.evaluate(&b), material.evaluate(&centralized));
+        }
+
+        #[test]
+        fn it_scores_classically_like_evaluate() {
+            let mut classical = Classical::default();
+            for fen in evaluate::POSITIONS.iter() {
+                let b = Board::from_fen(fen);
+                assert_eq!(classical.evaluate(&b), evaluate(&b), "{}", fen);
+            }
+
+            classical.new_game();
+            assert_eq!(classical.pawn_table().stats(), (0, 0));
+        }
+
+        #[test]
+        fn it_adds_reproducible_noise() {
+            let b = Board::default();
+            let scores = |seed| {
+                let mut random = Random::new(seed, 50);
+                (0..100).map(|_| random.evaluate(&b)).collect::<Vec<i32>>()
+            };
+
+            assert_eq!(scores(7), scores(7));
+            assert_ne!(scores(7), scores(8));
+            assert!(scores(7).iter().all(|score| score.abs() <= 50));
+        }
+
+        #[test]
+        fn it_evaluates_through_a_trait_object() {
+            let mut boxed: Box<dyn Evaluator> = Box::new(Material);
+            assert_eq!(boxed.evaluate(&Board::default()), 0);
+        }
+    }
+}
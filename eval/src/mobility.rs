@@ -0,0 +1,59 @@
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::constants::*;
+use crate::magic::tables::{KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS};
+use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+use crate::piece::{Color, Role};
+use crate::Score;
+
+// Indexed by Role. What every safe square a piece can move to is worth, and how many of them it is
+// expected to have, so pieces with fewer than that are penalized
+const MOBILITY: [Score; 6] = [
+    Score::ZERO,
+    Score::new(4, 4),
+    Score::new(5, 5),
+    Score::new(2, 4),
+    Score::new(1, 2),
+    Score::ZERO,
+];
+const EXPECTED_MOBILITY: [i32; 6] = [0, 4, 6, 7, 13, 0];
+
+// Kings and pawns are left to the king safety and pawn structure terms
+const MOBILE_ROLES: [Role; 4] = [Role::Knight, Role::Bishop, Role::Rook, Role::Queen];
+
+// The squares a #role of #color on #square attacks, with sliders stopped by #occupied
+pub fn attacks(color: Color, role: Role, square: Square, occupied: BitBoard) -> BitBoard {
+    let idx = square.index();
+    match role {
+        Role::Pawn => PAWN_ATTACKS[color.index()][idx],
+        Role::Knight => KNIGHT_ATTACKS[idx],
+        Role::Bishop => bishop_attacks(square.bitboard(), occupied),
+        Role::Rook => rook_attacks(square.bitboard(), occupied),
+        Role::Queen => queen_attacks(square.bitboard(), occupied),
+        Role::King => KING_ATTACKS[idx],
+    }
+}
+
+// Every square attacked by a pawn of #color
+pub fn pawn_attacks(board: &Board, color: Color) -> BitBoard {
+    let pawns = board.piece_bbs[color.index()][PAWNS_BB];
+    match color {
+        Color::White => pawns.north_east() | pawns.north_west(),
+        Color::Black => pawns.south_east() | pawns.south_west(),
+    }
+}
+
+// Counts the squares each knight, bishop, rook and queen of #color attacks that neither hold a
+// piece of its own nor are attacked by an enemy pawn. From #color's own point of view
+pub fn mobility(board: &Board, color: Color) -> Score {
+    let occupied = board.combined_bbs[ALL_PIECES_BB];
+    let safe = !board.color_bbs[color.index()] & !pawn_attacks(board, !color);
+    let pieces = board.piece_bbs[color.index()];
+
+    MOBILE_ROLES.iter().fold(Score::ZERO, |score, role| {
+        pieces[role.index()].squares().fold(score, |score, square| {
+            let count = (attacks(color, *role, square, occupied) & safe).popcnt() as i32;
+            score + MOBILITY[role.index()] * (count - EXPECTED_MOBILITY[role.index()])
+        })
+    })
+}
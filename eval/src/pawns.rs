@@ -0,0 +1,222 @@
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::constants::*;
+use crate::Score;
+
+// Indexed by the rank a passed pawn has reached, counted from its own side
+const PASSED: [Score; 8] = [
+    Score::new(0, 0),
+    Score::new(5, 10),
+    Score::new(10, 20),
+    Score::new(15, 35),
+    Score::new(30, 60),
+    Score::new(50, 100),
+    Score::new(80, 150),
+    Score::new(0, 0),
+];
+const CANDIDATE: Score = Score::new(5, 12);
+const CONNECTED: Score = Score::new(5, 8);
+const ISOLATED: Score = Score::new(-10, -15);
+const DOUBLED: Score = Score::new(-10, -20);
+const BACKWARD: Score = Score::new(-8, -10);
+const ISLAND: Score = Score::new(-5, -10);
+
+// Everything there is to know about the pawns of both sides, which only depends on where the pawns
+// stand. The sets of pawns are indexed by color
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PawnStructure {
+    // No enemy pawn in front of it or on an adjacent file that could stop it
+    pub passed: [BitBoard; 2],
+    // Not passed yet, but with a free file in front and at least as many pawns to help it through
+    // as there are enemy pawns guarding the way
+    pub candidates: [BitBoard; 2],
+    // No friendly pawn on either adjacent file
+    pub isolated: [BitBoard; 2],
+    // Every pawn with a friendly pawn behind it on the same file
+    pub doubled: [BitBoard; 2],
+    // Cannot be defended by a friendly pawn, and its next step forward is attacked by an enemy pawn
+    pub backward: [BitBoard; 2],
+    // Defended by a friendly pawn or standing next to one
+    pub connected: [BitBoard; 2],
+    // Groups of pawns on adjacent files
+    pub islands: [u32; 2],
+    // Whole files without any pawns
+    pub open_files: BitBoard,
+    // Whole files without pawns of the color, but with pawns of the other
+    pub half_open_files: [BitBoard; 2],
+    // The sum of the pawn terms of each color, from that side's own point of view
+    pub scores: [Score; 2],
+}
+
+impl PawnStructure {
+    pub fn new(white_pawns: BitBoard, black_pawns: BitBoard) -> PawnStructure {
+        // Black is analyzed on a flipped board, so both sides move north, and flipped back after
+        let white = SideStructure::new(white_pawns, black_pawns);
+        let black = SideStructure::new(black_pawns.flip_vertical(), white_pawns.flip_vertical());
+
+        let flip = |bb: BitBoard| bb.flip_vertical();
+        PawnStructure {
+            passed: [white.passed, flip(black.passed)],
+            candidates: [white.candidates, flip(black.candidates)],
+            isolated: [white.isolated, flip(black.isolated)],
+            doubled: [white.doubled, flip(black.doubled)],
+            backward: [white.backward, flip(black.backward)],
+            connected: [white.connected, flip(black.connected)],
+            islands: [white.islands, black.islands],
+            open_files: !(white_pawns | black_pawns).file_fill(),
+            half_open_files: [
+                !white_pawns.file_fill() & black_pawns.file_fill(),
+                !black_pawns.file_fill() & white_pawns.file_fill(),
+            ],
+            scores: [white.score(), black.score()],
+        }
+    }
+
+    pub fn from_board(board: &Board) -> PawnStructure {
+        PawnStructure::new(
+            board.piece_bbs[WHITE][PAWNS_BB],
+            board.piece_bbs[BLACK][PAWNS_BB],
+        )
+    }
+
+    // The pawn terms of both sides, from white's point of view
+    pub fn score(&self) -> Score {
+        self.scores[WHITE] - self.scores[BLACK]
+    }
+}
+
+// The pawn structure of one side, worked out as if it were white
+struct SideStructure {
+    passed: BitBoard,
+    candidates: BitBoard,
+    isolated: BitBoard,
+    doubled: BitBoard,
+    backward: BitBoard,
+    connected: BitBoard,
+    islands: u32,
+}
+
+impl SideStructure {
+    // Set-wise versions of the definitions on https://www.chessprogramming.org/Pawn_Structure
+    fn new(own: BitBoard, enemy: BitBoard) -> SideStructure {
+        let own_files = own.file_fill();
+        let adjacent_files = |bb: BitBoard| bb.file_fill().east() | bb.file_fill().west();
+
+        // The squares enemy pawns will pass through, and the squares they can ever attack
+        let enemy_front = enemy.south_fill().south();
+        let enemy_attack_span = enemy_front.east() | enemy_front.west();
+        let own_attack_span = own.north_fill().north_east() | own.north_fill().north_west();
+        let enemy_attacks = enemy.south_east() | enemy.south_west();
+
+        let passed = own & !(enemy_front | enemy_attack_span);
+        let backward = (own.north() & enemy_attacks & !own_attack_span).south() & own;
+
+        let candidates = (own & !passed & !enemy_front)
+            .squares()
+            .filter(|square| {
+                let pawn = square.bitboard();
+                let neighbors = adjacent_files(pawn);
+                let helpers = own & neighbors & pawn.rank_fill().south_fill();
+                let sentries = enemy & neighbors & pawn.rank_fill().north_fill().north();
+                helpers.popcnt() >= sentries.popcnt()
+            })
+            .collect();
+
+        // Files are counted along the first rank, where every island starts on a file whose
+        // western neighbor has no pawns
+        let files = own_files & RANK_1;
+        let islands = (files & !files.east()).popcnt();
+
+        SideStructure {
+            passed,
+            candidates,
+            isolated: own & !adjacent_files(own),
+            doubled: own & own.north_fill().north(),
+            backward,
+            connected: own & (own.north_east() | own.north_west() | own.east() | own.west()),
+            islands,
+        }
+    }
+
+    fn score(&self) -> Score {
+        let passed = self.passed.squares().fold(Score::ZERO, |score, square| {
+            score + PASSED[square.rank().index()]
+        });
+
+        passed
+            + CANDIDATE * self.candidates.popcnt() as i32
+            + CONNECTED * self.connected.popcnt() as i32
+            + ISOLATED * self.isolated.popcnt() as i32
+            + DOUBLED * self.doubled.popcnt() as i32
+            + BACKWARD * self.backward.popcnt() as i32
+            + ISLAND * self.islands.saturating_sub(1) as i32
+    }
+}
+
+// The Zobrist hash of the pawns alone, so positions that only differ in their pieces share it
+pub fn pawn_key(board: &Board) -> u64 {
+    board.pawn_hash
+}
+
+pub const DEFAULT_PAWN_TABLE_ENTRIES: usize = 1 << 12;
+
+#[derive(Clone, Copy)]
+struct PawnEntry {
+    key: u64,
+    structure: PawnStructure,
+}
+
+// Pawns move far less often than pieces, so the same structure comes up again and again in a
+// search. Entries are replaced whenever another structure needs their slot
+#[derive(Clone)]
+pub struct PawnHashTable {
+    entries: Vec<Option<PawnEntry>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl PawnHashTable {
+    // #entries is rounded up to a power of two
+    pub fn new(entries: usize) -> PawnHashTable {
+        PawnHashTable {
+            entries: vec![None; entries.max(1).next_power_of_two()],
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    // The pawn structure of #board, worked out and stored the first time it is asked for
+    pub fn probe(&mut self, board: &Board) -> PawnStructure {
+        let key = pawn_key(board);
+        let idx = key as usize & (self.entries.len() - 1);
+
+        if let Some(entry) = self.entries[idx] {
+            if entry.key == key {
+                self.hits += 1;
+                return entry.structure;
+            }
+        }
+
+        self.misses += 1;
+        let structure = PawnStructure::from_board(board);
+        self.entries[idx] = Some(PawnEntry { key, structure });
+        structure
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    // How many probes were answered from the table and how many had to work the structure out
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
+
+impl Default for PawnHashTable {
+    fn default() -> PawnHashTable {
+        PawnHashTable::new(DEFAULT_PAWN_TABLE_ENTRIES)
+    }
+}
@@ -0,0 +1,114 @@
+use crate::board::Board;
+use crate::constants::*;
+use crate::mobility::mobility;
+use crate::piece::Color;
+use crate::{phase, KingSafety, PawnStructure, Score, MAX_PHASE};
+
+use std::fmt;
+
+// Every term of the evaluation of a position, kept apart so they can be inspected one at a time.
+// Terms are indexed by color, each from that side's own point of view like Accumulators
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trace {
+    pub material: [Score; 2],
+    pub psqt: [Score; 2],
+    pub pawns: [Score; 2],
+    pub mobility: [Score; 2],
+    pub king_safety: [KingSafety; 2],
+    pub phase: i32,
+    pub side_to_move: Color,
+}
+
+impl Trace {
+    pub fn new(board: &Board, pawns: &PawnStructure) -> Trace {
+        let accumulators = board.accumulators;
+
+        Trace {
+            material: accumulators.material,
+            psqt: accumulators.psqt,
+            pawns: pawns.scores,
+            mobility: [mobility(board, Color::White), mobility(board, Color::Black)],
+            king_safety: [
+                KingSafety::new(board, Color::White, pawns),
+                KingSafety::new(board, Color::Black, pawns),
+            ],
+            phase: phase(board),
+            side_to_move: board.side_to_move,
+        }
+    }
+
+    // Each term by name, in the order they are printed
+    pub fn terms(&self) -> [(&'static str, [Score; 2]); 8] {
+        let [white, black] = self.king_safety;
+        [
+            ("material", self.material),
+            ("psqt", self.psqt),
+            ("pawns", self.pawns),
+            ("mobility", self.mobility),
+            ("king attack", [white.attack, black.attack]),
+            ("pawn shield", [white.shield, black.shield]),
+            ("pawn storm", [white.storm, black.storm]),
+            ("king files", [white.files, black.files]),
+        ]
+    }
+
+    // The sum of every term, from white's point of view
+    pub fn score(&self) -> Score {
+        self.terms().iter().fold(Score::ZERO, |score, (_, term)| {
+            score + term[WHITE] - term[BLACK]
+        })
+    }
+
+    // The tapered score from the perspective of the side to move, as returned by evaluate
+    pub fn evaluation(&self) -> i32 {
+        let score = self.score().taper(self.phase);
+
+        match self.side_to_move {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<12} | {:>11} | {:>11} | {:>11}",
+            "term", "white", "black", "total"
+        )?;
+        writeln!(
+            f,
+            "{:<12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+            "", "mg", "eg", "mg", "eg", "mg", "eg"
+        )?;
+        for (name, term) in self.terms().iter() {
+            let total = term[WHITE] - term[BLACK];
+            writeln!(
+                f,
+                "{:<12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                name,
+                term[WHITE].mg,
+                term[WHITE].eg,
+                term[BLACK].mg,
+                term[BLACK].eg,
+                total.mg,
+                total.eg
+            )?;
+        }
+
+        let score = self.score();
+        writeln!(
+            f,
+            "{:<12} | {:>11} | {:>11} | {:>5} {:>5}",
+            "total", "", "", score.mg, score.eg
+        )?;
+        write!(
+            f,
+            "phase {}/{}, {} for the side to move",
+            self.phase,
+            MAX_PHASE,
+            self.evaluation()
+        )
+    }
+}
@@ -1,7 +1,7 @@
 extern crate board;
-use crate::board::Board;
+use crate::board::{castling_rook_move, Board, UndoInfo};
 extern crate piece;
-use crate::piece::Pieces;
+use crate::piece::Color;
 extern crate movegen;
 use crate::movegen::MoveGen;
 extern crate chessmove;
@@ -11,32 +11,40 @@ use crate::constants::*;
 extern crate bitboard;
 use crate::bitboard::*;
 mod moment;
+pub mod pgn;
+mod san;
+mod status;
 use board::BoardParams;
 use moment::*;
+pub use san::{San, SanError};
+pub use status::GameStatus;
 
 #[derive(Clone)]
 pub struct Game {
     pub board: Board,
-    pub prev_board: Option<Board>,
     pub history: Vec<Moment>,
     pub future: Vec<Moment>,
+    // One entry for every move made since the board was last set directly, or since recording
+    // history was last switched on or off, so entries always line up with the moves on top of
+    // #history while it is recorded. Kept either way, so #undo works during search too
+    undo_stack: Vec<(ChessMove, UndoInfo)>,
     record_history: bool,
 }
 
 impl Game {
-    pub fn new(mut board: Board) -> Self {
-        let (checkers, pinned, attacked_squares) = MoveGen::calculate_derived_bitboards(&board);
-        board.checkers = checkers;
-        board.pinned = pinned;
-        board.attacked_squares = attacked_squares;
-
-        Game {
+    pub fn new(board: Board) -> Self {
+        let mut game = Game {
             board,
-            prev_board: None,
-            history: vec![Moment::new(board.to_fen(), (EMPTY, EMPTY))],
+            history: Vec::new(),
             future: Vec::new(),
+            undo_stack: Vec::new(),
             record_history: true,
-        }
+        };
+        MoveGen::update_derived_bitboards(&mut game.board);
+        game.history
+            .push(Moment::new(board.to_fen(), board.hash, None));
+
+        game
     }
 
     pub fn from_fen(fen: &str) -> Self {
@@ -46,295 +54,149 @@ impl Game {
 
     pub fn restart_game(&mut self) {
         self.board = Board::default();
-        self.record_moment((EMPTY, EMPTY));
+        self.undo_stack.clear();
+        self.record_moment(None);
     }
 
     pub fn restart_from_fen(&mut self, fen: &str) {
         self.board = Board::from_fen(fen);
-        self.record_moment((EMPTY, EMPTY));
+        self.undo_stack.clear();
+        self.record_moment(None);
     }
 
-    pub fn go_back(&mut self) -> (BitBoard, BitBoard) {
-        self.future.push(self.history.pop().unwrap());
+    // Moves played since the board was last set directly are taken back with Board::unmake_move,
+    // anything older is restored from its FEN
+    pub fn go_back(&mut self) -> Option<(Square, Square)> {
+        let moment = self.history.pop().unwrap();
+        let undo = moment.chessmove.and_then(|_| self.undo_stack.pop());
+        self.future.push(moment);
+
         let moment = self.history.last().unwrap();
-        self.board = Board::from_fen(moment.fen.as_str());
-        moment.last_move
+        let last_move = moment.last_move();
+        match undo {
+            Some((chessmove, undo)) => self.board.unmake_move(&chessmove, undo),
+            None => {
+                self.board = Board::from_fen(moment.fen.as_str());
+                MoveGen::update_derived_bitboards(&mut self.board);
+            }
+        }
+
+        last_move
     }
 
-    pub fn go_forward(&mut self) -> (BitBoard, BitBoard) {
-        self.history.push(self.future.pop().unwrap());
-        let moment = self.history.last().unwrap();
-        self.board = Board::from_fen(moment.fen.as_str());
-        moment.last_move
+    pub fn go_forward(&mut self) -> Option<(Square, Square)> {
+        let moment = self.future.pop().unwrap();
+        match moment.chessmove {
+            Some(chessmove) => {
+                let undo = self.board.make_move(&chessmove);
+                self.undo_stack.push((chessmove, undo));
+            }
+            None => {
+                self.board = Board::from_fen(moment.fen.as_str());
+                self.undo_stack.clear();
+            }
+        }
+        MoveGen::update_derived_bitboards(&mut self.board);
+
+        let last_move = moment.last_move();
+        self.history.push(moment);
+        last_move
+    }
+
+    pub fn set_record_history(&mut self, record_history: bool) {
+        if record_history != self.record_history {
+            self.undo_stack.clear();
+        }
+        self.record_history = record_history;
     }
 
-    pub fn record_moment(&mut self, last_move: (BitBoard, BitBoard)) {
+    pub fn record_moment(&mut self, chessmove: Option<ChessMove>) {
         if self.record_history {
             let board_fen = self.board.to_fen();
-            self.history.push(Moment::new(board_fen, last_move));
+            self.history
+                .push(Moment::new(board_fen, self.board.hash, chessmove));
             self.future = Vec::new();
         }
     }
 
-    //TODO test
     //Returns list of all moved pieces, including castling rook
-    pub fn make_move(&mut self, chessmove: &ChessMove) -> Vec<(BitBoard, BitBoard)> {
-        self.prev_board = Some(self.board);
-        let prev_en_passant = self.board.en_passant;
-        self.board.en_passant = EMPTY;
-        let mut moves: Vec<(BitBoard, BitBoard)> = Vec::new();
-
+    pub fn make_move(&mut self, chessmove: &ChessMove) -> Vec<(Square, Square)> {
         let moving_piece = self.board.get_piece_at(chessmove.from);
-        let target_piece = self.board.get_piece_at(chessmove.to);
+        let undo = self.board.make_move(chessmove);
 
-        self.board.move_piece(chessmove.from, chessmove.to);
+        MoveGen::update_derived_bitboards(&mut self.board);
+        self.undo_stack.push((*chessmove, undo));
+        self.record_moment(Some(*chessmove));
 
-        moves.push((chessmove.from, chessmove.to));
+        let mut moves = vec![(chessmove.from, chessmove.to)];
+        moves.extend(castling_rook_move(moving_piece, chessmove));
+        moves
+    }
 
-        match target_piece {
-            Pieces::WRook => {
-                if chessmove.to == A1_SQUARE {
-                    self.board.castle_rights &= !C1_SQUARE;
-                } else if chessmove.to == H1_SQUARE {
-                    self.board.castle_rights &= !G1_SQUARE;
-                }
-            }
-            Pieces::BRook => {
-                if chessmove.to == A8_SQUARE {
-                    self.board.castle_rights &= !C8_SQUARE;
-                } else if chessmove.to == H8_SQUARE {
-                    self.board.castle_rights &= !G8_SQUARE;
-                }
-            }
-            Pieces::WKing => {
-                panic!("King invalidly captured");
-            }
-            Pieces::BKing => {
-                panic!("King invalidly captured");
+    // Takes back the last move played with #make_move, as many times as there are moves to take
+    // back. Does nothing once there are none left, see #undo_stack
+    pub fn undo(&mut self) {
+        if let Some((chessmove, undo)) = self.undo_stack.pop() {
+            self.board.unmake_move(&chessmove, undo);
+            if self.record_history {
+                self.history.pop();
             }
-            _ => (),
         }
+    }
 
-        match moving_piece {
-            Pieces::WPawn => {
-                if chessmove.to == prev_en_passant {
-                    let piece_to_remove = prev_en_passant.shr(8);
-                    self.board.piece_bbs[BLACK][PAWNS_BB] ^= piece_to_remove;
-                    self.board.color_bbs[BLACK] ^= piece_to_remove;
-                    self.board.combined_bbs[ALL_PAWNS_BB] ^= piece_to_remove;
-
-                    self.board.combined_bbs[EMPTY_SQUARES_BB] |= piece_to_remove;
-                    self.board.combined_bbs[ALL_PIECES_BB] ^= piece_to_remove;
-                }
-
-                if chessmove.from.shl(16) == chessmove.to {
-                    self.board.en_passant = chessmove.from.shl(8);
-                }
-            }
-            Pieces::BPawn => {
-                if chessmove.to == prev_en_passant {
-                    let piece_to_remove = prev_en_passant.shl(8);
-                    self.board.piece_bbs[WHITE][PAWNS_BB] ^= piece_to_remove;
-                    self.board.color_bbs[WHITE] ^= piece_to_remove;
-                    self.board.combined_bbs[ALL_PAWNS_BB] ^= piece_to_remove;
-
-                    self.board.combined_bbs[EMPTY_SQUARES_BB] |= piece_to_remove;
-                    self.board.combined_bbs[ALL_PIECES_BB] ^= piece_to_remove;
-                }
-
-                if chessmove.from.shr(16) == chessmove.to {
-                    self.board.en_passant = chessmove.from.shr(8);
-                }
-            }
-            Pieces::WRook => {
-                if chessmove.from == A1_SQUARE {
-                    self.board.castle_rights &= !C1_SQUARE;
-                } else if chessmove.from == H1_SQUARE {
-                    self.board.castle_rights &= !G1_SQUARE;
-                }
-            }
-            Pieces::BRook => {
-                if chessmove.from == A8_SQUARE {
-                    self.board.castle_rights &= !C8_SQUARE;
-                } else if chessmove.from == H8_SQUARE {
-                    self.board.castle_rights &= !G8_SQUARE;
-                }
-            }
-            Pieces::WKing => {
-                if chessmove.from == E1_SQUARE && chessmove.to == G1_SQUARE {
-                    self.board.move_piece(H1_SQUARE, F1_SQUARE);
-                    moves.push((H1_SQUARE, F1_SQUARE));
-                } else if chessmove.from == E1_SQUARE && chessmove.to == C1_SQUARE {
-                    self.board.move_piece(A1_SQUARE, D1_SQUARE);
-                    moves.push((A1_SQUARE, D1_SQUARE));
-                }
-
-                self.board.castle_rights &= !(C1_SQUARE | G1_SQUARE);
-            }
-            Pieces::BKing => {
-                if chessmove.from == E8_SQUARE && chessmove.to == G8_SQUARE {
-                    self.board.move_piece(H8_SQUARE, F8_SQUARE);
-                    moves.push((H8_SQUARE, F8_SQUARE));
-                } else if chessmove.from == E8_SQUARE && chessmove.to == C8_SQUARE {
-                    self.board.move_piece(A8_SQUARE, D8_SQUARE);
-                    moves.push((A8_SQUARE, D8_SQUARE));
-                }
-
-                self.board.castle_rights &= !(C8_SQUARE | G8_SQUARE);
-            }
-            _ => (),
+    // Checkmate and stalemate take precedence over every draw. Repetitions are only counted while
+    // history is being recorded
+    pub fn status(&self) -> GameStatus {
+        if MoveGen::gen_legal_moves(&self.board).is_empty() {
+            return if self.board.checkers.is_not_empty() {
+                GameStatus::Checkmate(!self.board.side_to_move)
+            } else {
+                GameStatus::Stalemate
+            };
         }
 
-        if let Some(promotion) = chessmove.promotion {
-            self.board.piece_bbs[moving_piece.color_bb_index()]
-                [moving_piece.piece_by_color_bb_index()] ^= chessmove.to;
-            self.board.combined_bbs[moving_piece.combined_color_bb_index()] ^= chessmove.to;
-
-            self.board.piece_bbs[promotion.color_bb_index()]
-                [promotion.piece_by_color_bb_index()] |= chessmove.to;
-            self.board.combined_bbs[promotion.combined_color_bb_index()] |= chessmove.to;
-            moves.push((EMPTY, chessmove.to));
+        if status::is_insufficient_material(&self.board) {
+            GameStatus::InsufficientMaterial
+        } else if self.board.half_moves_since_action >= 150 {
+            GameStatus::SeventyFiveMove
+        } else if self.repetitions() >= 3 {
+            GameStatus::ThreefoldRepetition
+        } else if self.board.half_moves_since_action >= 100 {
+            GameStatus::FiftyMoveRule
+        } else {
+            GameStatus::Ongoing
         }
-        self.board.switch_side_to_move();
-        let (checkers, pinned, attacked_squares) =
-            MoveGen::calculate_derived_bitboards(&self.board);
-        self.board.checkers = checkers;
-        self.board.pinned = pinned;
-        self.board.attacked_squares = attacked_squares;
+    }
 
-        self.record_moment((chessmove.from, chessmove.to));
-        moves
+    // Number of times the current position has occurred, including now. A capture or pawn move can
+    // never be undone, so only the moments since the last one are compared
+    pub fn repetitions(&self) -> usize {
+        self.history
+            .iter()
+            .rev()
+            .take(self.board.half_moves_since_action as usize + 1)
+            .filter(|moment| moment.hash == self.board.hash)
+            .count()
     }
 
-    pub fn undo(&mut self) {
-        if let Some(prev_board) = self.prev_board {
-            self.history.pop();
-            self.board = prev_board;
+    // Pieces are placed independently of each other, so boards are drawn until one passes
+    // Board::validate, e.g. with the kings apart and black not in check
+    pub fn randomize_board(&mut self) -> &Self {
+        let mut rng = rand::thread_rng();
+        let mut board = Game::random_board(&mut rng);
+        while board.validate().is_err() {
+            board = Game::random_board(&mut rng);
         }
-    }
 
-    // // TODO test everything else first
-    // //TODO test
-    // pub fn get_strategic_eval(_board: &Board) -> i32 {
-    //     //Consider phase of game
-    //     //Consider board openness
-    //     0
-    // }
-
-    // //TODO test
-    // pub fn get_positional_eval(_board: &Board) -> i32 {
-    //     //Consider #attacked squares
-    //     //Consider castle rights
-    //     //Consider strong positions by pieces
-    //     //Consdier #attacked pieces
-    //     //Consider #
-    //     0
-    // }
-
-    // //TODO test
-    // pub fn get_material_eval(board: &Board) -> i32 {
-    //     let (white, black) = board.get_material_eval();
-
-    //     if board.side_to_move == WHITE {
-    //         white as i32 - black as i32
-    //     } else {
-    //         black as i32 - white as i32
-    //     }
-    // }
-
-    // //Always from white perspective
-    // //TODO test
-    // pub fn get_full_eval(board: &Board) -> i32 {
-    //     let material = Game::get_material_eval(board);
-    //     let positional = Game::get_positional_eval(board);
-    //     let strategic = Game::get_strategic_eval(board);
-    //     material + positional + strategic
-    // }
-
-    // //TODO test
-    // pub fn get_best_move(&mut self, depth: u8) -> Option<(BitBoard, BitBoard)> {
-    //     let mut g = self.clone();
-
-    //     g.record_history = false;
-
-    //     let (_eval_score, chessmove) = g.get_best_move_recursive(depth, true, i32::MIN, i32::MAX);
-
-    //     println!("{}", _eval_score);
-
-    //     chessmove
-    // }
-
-    // pub fn get_best_move_recursive(
-    //     &mut self,
-    //     depth: u8,
-    //     is_maximizer: bool,
-    //     mut alpha: i32,
-    //     mut beta: i32,
-    // ) -> (i32, Option<(BitBoard, BitBoard)>) {
-    //     if depth == 0 {
-    //         let eval_score = Game::get_full_eval(&self.board);
-    //         if is_maximizer {
-    //             return (eval_score, None);
-    //         } else {
-    //             return (-eval_score, None);
-    //         }
-    //     }
-
-    //     let mut best_move: Option<(BitBoard, BitBoard)> = None;
-    //     let mut best_move_value = match is_maximizer {
-    //         true => i32::MIN,
-    //         false => i32::MAX,
-    //     };
-    //     let mut value: i32;
-
-    //     let valid_moves = ChessMove::broken_up(MoveGen::gen_legal_moves(&self.board));
-
-    //     let mut valid_moves_with_eval: Vec<(&ChessMove, i32, Board)> = valid_moves
-    //         .iter()
-    //         .map(|chessmove| {
-    //             // let mut cloned = self.clone();
-    //             self.make_move(chessmove);
-    //             let board = self.board;
-    //             let eval = Game::get_material_eval(&board);
-    //             self.undo();
-    //             (chessmove, eval, board)
-    //         })
-    //         .collect();
-
-    //     valid_moves_with_eval.sort_by(|l, r| l.1.partial_cmp(&r.1).unwrap());
-
-    //     for valid_move in valid_moves_with_eval {
-    //         self.board = valid_move.2;
-    //         value = self
-    //             .get_best_move_recursive(depth - 1, !is_maximizer, alpha, beta)
-    //             .0;
-
-    //         if is_maximizer {
-    //             if value > best_move_value {
-    //                 best_move_value = value;
-    //                 best_move = Some((valid_move.0.from, valid_move.0.to));
-    //             }
-    //             alpha = std::cmp::max(alpha, value);
-    //         } else {
-    //             if value < best_move_value {
-    //                 best_move_value = value;
-    //                 best_move = Some((valid_move.0.from, valid_move.0.to));
-    //             }
-    //             beta = std::cmp::min(beta, value);
-    //         }
-
-    //         if beta <= alpha {
-    //             break;
-    //         }
-    //     }
-
-    //     (best_move_value, best_move)
-    // }
+        MoveGen::update_derived_bitboards(&mut board);
+        self.board = board;
+        self.undo_stack.clear();
 
-    pub fn randomize_board(&mut self) -> &Self {
-        use rand::{thread_rng, Rng};
-        let mut rng = thread_rng();
+        self.record_moment(None);
+        self
+    }
 
+    fn random_board<R: rand::Rng>(rng: &mut R) -> Board {
         let mut white_pawns = EMPTY;
         let mut taken_squares = EMPTY;
 
@@ -431,7 +293,7 @@ impl Game {
             black_kings |= square & !taken_squares;
         }
 
-        self.board = Board::new(BoardParams {
+        Board::new(BoardParams {
             white_pawns,
             white_knights,
             white_bishops,
@@ -444,15 +306,12 @@ impl Game {
             black_rooks,
             black_queens,
             black_kings,
-            side_to_move: Some(WHITE),
+            side_to_move: Some(Color::White),
             castle_rights: None,
             en_passant: None,
             half_moves_since_action: None,
             full_moves: None,
-        });
-
-        self.record_moment((EMPTY, EMPTY));
-        self
+        })
     }
 }
 
@@ -466,6 +325,7 @@ impl Default for Game {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use piece::Pieces;
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
@@ -481,11 +341,502 @@ mod tests {
             g.make_move(&ChessMove::from_notation("F7", "F5"));
             g.make_move(&ChessMove::from_notation("D1", "H5"));
 
-            assert_eq!(g.board.get_piece_at(F5_SQUARE), Pieces::BPawn);
+            assert_eq!(g.board.get_piece_at(Square::F5), Pieces::BPawn);
             assert_eq!(g.board.checkers, H5_SQUARE);
         }
     }
 
+    mod undo {
+        use super::*;
+
+        const MOVES: [(&str, &str); 6] = [
+            ("E2", "E4"),
+            ("D7", "D5"),
+            ("E4", "D5"),
+            ("D8", "D5"),
+            ("B1", "C3"),
+            ("D5", "E5"),
+        ];
+
+        #[test]
+        fn it_takes_back_every_move() {
+            let mut g = Game::default();
+            let mut boards = vec![g.board];
+            for (from, to) in MOVES.iter() {
+                g.make_move(&ChessMove::from_notation(from, to));
+                boards.push(g.board);
+            }
+
+            while boards.len() > 1 {
+                boards.pop();
+                g.undo();
+                assert_eq!(g.board, *boards.last().unwrap());
+            }
+            assert_eq!(g.history.len(), 1);
+
+            g.undo();
+            assert_eq!(g.board, boards[0]);
+        }
+
+        #[test]
+        fn it_steps_through_history() {
+            let mut g = Game::default();
+            for (from, to) in MOVES.iter() {
+                g.make_move(&ChessMove::from_notation(from, to));
+            }
+            let end = g.board;
+
+            for _ in 0..MOVES.len() {
+                g.go_back();
+            }
+            assert_eq!(g.board, Game::default().board);
+
+            let mut last_move = None;
+            for _ in 0..MOVES.len() {
+                last_move = g.go_forward();
+            }
+            assert_eq!(g.board, end);
+            assert_eq!(last_move, Some((Square::D5, Square::E5)));
+            assert_eq!(g.board.checkers, E5_SQUARE);
+        }
+
+        #[test]
+        fn it_keeps_unrecorded_moves_out_of_history() {
+            let mut g = Game::default();
+            g.make_move(&ChessMove::from_notation("E2", "E4"));
+            g.set_record_history(false);
+            g.make_move(&ChessMove::from_notation("E7", "E5"));
+            g.set_record_history(true);
+
+            // The last recorded move is no longer the last one played, so nothing is taken back
+            g.undo();
+            assert_eq!(g.history.len(), 2);
+
+            g.make_move(&ChessMove::from_notation("G1", "F3"));
+            g.go_back();
+            g.go_back();
+            assert_eq!(g.board, Game::default().board);
+            assert_eq!(g.history.len(), 1);
+        }
+
+        #[test]
+        fn it_steps_back_past_a_restart() {
+            let mut g = Game::default();
+            g.make_move(&ChessMove::from_notation("E2", "E4"));
+            let before_restart = g.board;
+            g.restart_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+            g.make_move(&ChessMove::from_notation("A1", "A8"));
+
+            g.go_back();
+            assert_eq!(g.board.to_fen(), "4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+            g.go_back();
+            assert_eq!(g.board, before_restart);
+        }
+    }
+
+    mod clocks {
+        use super::*;
+
+        #[test]
+        fn it_updates_the_move_counters() {
+            let mut g = Game::default();
+            g.make_move(&ChessMove::from_notation("E2", "E4"));
+            g.make_move(&ChessMove::from_notation("C7", "C5"));
+            g.make_move(&ChessMove::from_notation("G1", "F3"));
+
+            assert_eq!(
+                g.board.to_fen(),
+                "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+            );
+        }
+
+        #[test]
+        fn it_resets_the_half_move_clock_on_captures() {
+            let mut g = Game::from_fen("4k3/8/8/3p4/8/8/8/3RK3 w - - 20 40");
+            g.make_move(&ChessMove::from_notation("D1", "D5"));
+
+            assert_eq!(g.board.half_moves_since_action, 0);
+            assert_eq!(g.board.full_moves, 40);
+        }
+    }
+
+    mod status {
+        use super::*;
+
+        #[test]
+        fn it_detects_checkmate() {
+            let mut g = Game::default();
+            g.make_move(&ChessMove::from_notation("F2", "F3"));
+            g.make_move(&ChessMove::from_notation("E7", "E5"));
+            g.make_move(&ChessMove::from_notation("G2", "G4"));
+            assert_eq!(g.status(), GameStatus::Ongoing);
+
+            g.make_move(&ChessMove::from_notation("D8", "H4"));
+            assert_eq!(g.status(), GameStatus::Checkmate(Color::Black));
+        }
+
+        #[test]
+        fn it_detects_stalemate() {
+            let g = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+
+            assert_eq!(g.status(), GameStatus::Stalemate);
+        }
+
+        #[test]
+        fn it_detects_threefold_repetition() {
+            let mut g = Game::default();
+            for _ in 0..2 {
+                g.make_move(&ChessMove::from_notation("G1", "F3"));
+                g.make_move(&ChessMove::from_notation("G8", "F6"));
+                g.make_move(&ChessMove::from_notation("F3", "G1"));
+                assert_eq!(g.status(), GameStatus::Ongoing);
+                g.make_move(&ChessMove::from_notation("F6", "G8"));
+            }
+
+            assert_eq!(g.repetitions(), 3);
+            assert_eq!(g.status(), GameStatus::ThreefoldRepetition);
+        }
+
+        #[test]
+        fn it_ignores_en_passant_squares_no_pawn_can_take_on() {
+            let mut g =
+                Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+            for _ in 0..2 {
+                g.make_move(&ChessMove::from_notation("G8", "F6"));
+                g.make_move(&ChessMove::from_notation("G1", "F3"));
+                g.make_move(&ChessMove::from_notation("F6", "G8"));
+                g.make_move(&ChessMove::from_notation("F3", "G1"));
+            }
+
+            assert_eq!(g.repetitions(), 3);
+            assert_eq!(g.status(), GameStatus::ThreefoldRepetition);
+        }
+
+        #[test]
+        fn it_detects_the_fifty_and_seventy_five_move_rules() {
+            let mut g = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80");
+            g.make_move(&ChessMove::from_notation("A1", "A2"));
+            assert_eq!(g.status(), GameStatus::FiftyMoveRule);
+
+            let mut g = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 100");
+            g.make_move(&ChessMove::from_notation("A1", "A2"));
+            assert_eq!(g.status(), GameStatus::SeventyFiveMove);
+        }
+
+        #[test]
+        fn it_detects_insufficient_material() {
+            let drawn = [
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+                "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            ];
+            let playable = [
+                "4k3/8/8/8/8/8/8/4KNN1 w - - 0 1",
+                "4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1",
+                "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            ];
+
+            for fen in drawn.iter() {
+                assert_eq!(
+                    Game::from_fen(fen).status(),
+                    GameStatus::InsufficientMaterial,
+                    "{}",
+                    fen
+                );
+            }
+            for fen in playable.iter() {
+                assert_eq!(Game::from_fen(fen).status(), GameStatus::Ongoing, "{}", fen);
+            }
+        }
+    }
+
+    mod san {
+        use super::*;
+
+        fn assert_round_trip(fen: &str, chessmove: ChessMove, san: &str) {
+            let b = Board::from_fen(fen);
+
+            assert_eq!(chessmove.to_san(&b), san);
+            assert_eq!(ChessMove::from_san(&b, san), Ok(chessmove));
+        }
+
+        #[test]
+        fn it_handles_pawn_and_piece_moves() {
+            let fen = "rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w KQkq - 0 2";
+
+            assert_round_trip(fen, ChessMove::new(Square::E2, Square::E4), "e4");
+            assert_round_trip(fen, ChessMove::new(Square::D4, Square::E5), "dxe5");
+            assert_round_trip(fen, ChessMove::new(Square::G1, Square::F3), "Nf3");
+            assert_round_trip(fen, ChessMove::new(Square::C1, Square::G5), "Bg5");
+        }
+
+        #[test]
+        fn it_disambiguates() {
+            let fen = "4k3/8/8/8/8/R7/8/R3K1N1 w - - 0 1";
+            assert_round_trip(fen, ChessMove::new(Square::A1, Square::A2), "R1a2");
+            assert_round_trip(fen, ChessMove::new(Square::A3, Square::A2), "R3a2");
+
+            let fen = "r3k3/8/8/8/8/8/8/1N2KN2 w q - 0 1";
+            assert_round_trip(fen, ChessMove::new(Square::B1, Square::D2), "Nbd2");
+
+            let fen = "4k3/8/8/8/8/Q1Q5/8/Q3K3 w - - 0 1";
+            assert_round_trip(fen, ChessMove::new(Square::A3, Square::B2), "Qa3b2");
+        }
+
+        #[test]
+        fn it_handles_castling() {
+            let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+            assert_round_trip(fen, ChessMove::new(Square::E1, Square::G1), "O-O");
+            assert_round_trip(fen, ChessMove::new(Square::E1, Square::C1), "O-O-O");
+            assert_eq!(
+                ChessMove::from_san(&Board::from_fen(fen), "0-0"),
+                Ok(ChessMove::new(Square::E1, Square::G1))
+            );
+        }
+
+        #[test]
+        fn it_handles_promotions_and_en_passant() {
+            let fen = "1n2k3/P7/8/3pP3/8/8/8/4K3 w - d6 0 1";
+
+            assert_round_trip(
+                fen,
+                ChessMove::promote(Square::A7, Square::A8, Pieces::WQueen),
+                "a8=Q",
+            );
+            assert_round_trip(
+                fen,
+                ChessMove::promote(Square::A7, Square::B8, Pieces::WKnight),
+                "axb8=N",
+            );
+            assert_round_trip(fen, ChessMove::new(Square::E5, Square::D6), "exd6");
+        }
+
+        #[test]
+        fn it_adds_check_and_mate_suffixes() {
+            let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+            assert_round_trip(fen, ChessMove::new(Square::A1, Square::A8), "Ra8#");
+            assert_round_trip(
+                "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+                ChessMove::new(Square::A1, Square::A8),
+                "Ra8+",
+            );
+            assert_eq!(
+                ChessMove::from_san(&Board::from_fen(fen), "Ra8"),
+                Ok(ChessMove::new(Square::A1, Square::A8))
+            );
+        }
+
+        #[test]
+        fn it_rejects_bad_input() {
+            let b = Board::default();
+
+            assert_eq!(
+                ChessMove::from_san(&b, "e5"),
+                Err(SanError::IllegalMove(String::from("e5")))
+            );
+            assert_eq!(
+                ChessMove::from_san(&b, "Zz9"),
+                Err(SanError::InvalidSyntax(String::from("Zz9")))
+            );
+            assert_eq!(
+                ChessMove::from_san(&b, ""),
+                Err(SanError::InvalidSyntax(String::new()))
+            );
+
+            let b = Board::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+            assert_eq!(
+                ChessMove::from_san(&b, "Nd2"),
+                Err(SanError::AmbiguousMove(String::from("Nd2")))
+            );
+        }
+    }
+
+    mod pgn {
+        use super::*;
+        use crate::board::FenError;
+        use crate::pgn::*;
+
+        const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1.e4 e5 2.Nf3 d6 3.d4 Bg4 {This is a weak move already.} 4.dxe5 Bxf3 5.Qxf3 dxe5
+6.Bc4 Nf6 7.Qb3 $1 Qe7 8.Nc3 (8.Qxb7 Qb4+ 9.Qxb4 Bxb4+) c6 9.Bg5 b5 10.Nxb5 cxb5
+11.Bxb5+ Nbd7 12.O-O-O Rd8 13.Rxd7 Rxd7 14.Rd1 Qe6 15.Bxd7+ Nxd7 16.Qb8+ Nxb8
+17.Rd8# 1-0
+"#;
+
+        #[test]
+        fn it_reads_a_game() {
+            let pgn = read_pgn(OPERA_GAME).unwrap();
+
+            assert_eq!(pgn.tag("White"), Some("Paul Morphy"));
+            assert_eq!(pgn.result, "1-0");
+            assert_eq!(pgn.game.history.len(), 34);
+            assert_eq!(pgn.game.status(), GameStatus::Checkmate(Color::White));
+            assert_eq!(
+                pgn.game.board.to_fen(),
+                "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17"
+            );
+        }
+
+        #[test]
+        fn it_streams_multiple_games() {
+            let pgn = format!(
+                "{}\n[Event \"Second\"]\n\n1. d4 d5 *\n\n{{Only a comment}} 1. c4 1/2-1/2\n",
+                OPERA_GAME
+            );
+            let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes())
+                .map(|game| game.unwrap())
+                .collect();
+
+            assert_eq!(games.len(), 3);
+            assert_eq!(games[1].tag("Event"), Some("Second"));
+            assert_eq!(games[1].result, "*");
+            assert_eq!(games[1].game.history.len(), 3);
+            assert!(games[2].tags.is_empty());
+            assert_eq!(games[2].result, "1/2-1/2");
+            assert_eq!(games[2].game.history.len(), 2);
+        }
+
+        #[test]
+        fn it_ignores_results_in_comments_and_variations() {
+            let pgn = "1. e4 {1-0 would be premature}\ne5 (1... d5 0-1) 2. Nf3 ; 1/2-1/2\n*\n";
+            let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes())
+                .map(|game| game.unwrap())
+                .collect();
+
+            assert_eq!(games.len(), 1);
+            assert_eq!(games[0].result, "*");
+            assert_eq!(games[0].game.history.len(), 4);
+        }
+
+        #[test]
+        fn it_reports_illegal_moves() {
+            match read_pgn("1. e4 e5 2. Ke3 *") {
+                Err(PgnError::InvalidMove { ply, .. }) => assert_eq!(ply, 3),
+                _ => panic!("Expected an invalid move"),
+            }
+            assert!(matches!(
+                read_pgn("[Event Paris]\n1. e4 *"),
+                Err(PgnError::InvalidTag(_))
+            ));
+            assert!(matches!(
+                read_pgn("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n*"),
+                Err(PgnError::InvalidFen(FenError::MissingKing(Color::White)))
+            ));
+        }
+
+        #[test]
+        fn it_writes_the_seven_tag_roster() {
+            let mut g = Game::default();
+            g.make_move(&ChessMove::from_notation("E2", "E4"));
+            g.make_move(&ChessMove::from_notation("E7", "E5"));
+            let tags = vec![
+                (String::from("White"), String::from("Me")),
+                (String::from("Annotator"), String::from("\"Someone\"")),
+            ];
+
+            assert_eq!(
+                write_pgn(&g, &tags),
+                "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+                 [White \"Me\"]\n[Black \"?\"]\n[Result \"*\"]\n[Annotator \"\\\"Someone\\\"\"]\n\n\
+                 1. e4 e5 *\n"
+            );
+        }
+
+        #[test]
+        fn it_round_trips() {
+            let pgn = read_pgn(OPERA_GAME).unwrap();
+            let written = write_pgn(&pgn.game, &pgn.tags);
+            let reread = read_pgn(&written).unwrap();
+
+            assert_eq!(reread.tags, pgn.tags);
+            assert_eq!(reread.game.board, pgn.game.board);
+            assert!(written.lines().all(|line| line.len() <= 80));
+        }
+
+        #[test]
+        fn it_writes_setup_positions_and_promotions() {
+            let fen = "4k3/1P5p/8/8/8/8/8/4K3 b - - 0 40";
+            let mut g = Game::from_fen(fen);
+            g.make_move(&ChessMove::from_notation("E8", "D7"));
+            g.make_move(&ChessMove::promote(Square::B7, Square::B8, Pieces::WKnight));
+
+            let written = write_pgn(&g, &[]);
+            assert!(written.contains(&format!("[FEN \"{}\"]", fen)));
+            assert!(written.ends_with("40... Kd7 41. b8=N+ *\n"));
+            assert_eq!(read_pgn(&written).unwrap().game.board, g.board);
+        }
+
+        #[test]
+        fn it_only_writes_draws_nobody_has_to_claim() {
+            let claimable = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 100 80");
+            assert_eq!(claimable.status(), GameStatus::FiftyMoveRule);
+            assert!(write_pgn(&claimable, &[]).contains("[Result \"*\"]"));
+
+            let automatic = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 150 100");
+            assert_eq!(automatic.status(), GameStatus::SeventyFiveMove);
+            assert!(write_pgn(&automatic, &[]).contains("[Result \"1/2-1/2\"]"));
+        }
+    }
+
+    mod randomize_board {
+        use super::*;
+
+        #[test]
+        fn it_only_produces_legal_positions() {
+            let mut g = Game::default();
+            for _ in 0..50 {
+                g.randomize_board();
+
+                assert_eq!(g.board.validate(), Ok(()));
+                assert_eq!(Board::from_fen(&g.board.to_fen()).hash, g.board.hash);
+            }
+        }
+    }
+
+    mod hash {
+        use super::*;
+
+        fn play(g: &mut Game, moves: &[(&str, &str)]) {
+            for (from, to) in moves {
+                g.make_move(&ChessMove::from_notation(from, to));
+            }
+        }
+
+        #[test]
+        fn it_matches_after_a_transposition() {
+            let mut g = Game::default();
+            play(
+                &mut g,
+                &[("G1", "F3"), ("G8", "F6"), ("F3", "G1"), ("F6", "G8")],
+            );
+
+            assert_eq!(g.board.hash, Board::default().hash);
+        }
+
+        #[test]
+        fn it_tracks_castling_en_passant_and_promotion() {
+            let mut g = Game::from_fen("r3k2r/1P6/8/8/5p2/8/4P3/R3K2R w KQkq - 0 1");
+            play(
+                &mut g,
+                &[("E2", "E4"), ("F4", "E3"), ("E1", "G1"), ("E8", "C8")],
+            );
+            g.make_move(&ChessMove::promote(Square::B7, Square::B8, Pieces::WQueen));
+
+            let expected = Board::from_fen("1Qkr3r/8/8/8/8/4p3/8/R4RK1 b - - 0 3");
+            assert_eq!(g.board.hash, expected.hash);
+            assert_eq!(g.board.hash, g.board.compute_hash());
+        }
+    }
+
     mod north_east_attacks {
         use super::*;
 
@@ -517,33 +868,11 @@ mod tests {
             g.board.attacked_squares.print_bb("Attackers");
             g.board.print_board();
             for chessmove in moves {
-                if chessmove.from == E1_SQUARE {
-                    chessmove.from.print_bb("From");
-                    chessmove.to.print_bb("To");
+                if chessmove.from == Square::E1 {
+                    chessmove.from.bitboard().print_bb("From");
+                    chessmove.to.bitboard().print_bb("To");
                 }
             }
         }
-
-        // #[test]
-        // fn it_works() {
-        //     let mut g =
-        //         Game::from_fen("rn1qkbnr/1pp2ppp/p7/3pp3/4P1b1/3P4/PPP2PPP/RNB1KBNR w kq - 0 1");
-        //     let best_move = g.get_best_move(6);
-        //     if let Some(chessmove) = best_move {
-        //         chessmove.0.print_bb("From");
-        //         chessmove.1.print_bb("To");
-        //     }
-        // }
-
-        // #[test]
-        // fn it_works2() {
-        //     let mut g =
-        //         Game::from_fen("r1bqkbnr/1ppp1pp1/p1n4p/4p3/4P3/1P3N2/PBPP1PPP/RN1QKB1R w KQkq - 0 1");
-        //     let best_move = g.get_best_move(3);
-        //     if let Some(chessmove) = best_move {
-        //         chessmove.0.print_bb("From");
-        //         chessmove.1.print_bb("To");
-        //     }
-        // }
     }
 }
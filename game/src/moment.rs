@@ -1,13 +1,25 @@
-use bitboard::BitBoard;
+use bitboard::Square;
+use chessmove::ChessMove;
 
 #[derive(Clone)]
 pub struct Moment {
-    pub last_move: (BitBoard, BitBoard),
+    // None for the starting position and for positions set up without a move
+    pub chessmove: Option<ChessMove>,
     pub fen: String,
+    pub hash: u64,
 }
 
 impl Moment {
-    pub fn new(fen: String, last_move: (BitBoard, BitBoard)) -> Moment {
-        Moment { fen, last_move }
+    pub fn new(fen: String, hash: u64, chessmove: Option<ChessMove>) -> Moment {
+        Moment {
+            fen,
+            hash,
+            chessmove,
+        }
+    }
+
+    pub fn last_move(&self) -> Option<(Square, Square)> {
+        self.chessmove
+            .map(|chessmove| (chessmove.from, chessmove.to))
     }
 }
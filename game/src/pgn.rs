@@ -0,0 +1,386 @@
+use crate::board::{Board, FenError};
+use crate::chessmove::ChessMove;
+use crate::piece::Color;
+use crate::{Game, GameStatus, San, SanError};
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    InvalidTag(String),
+    InvalidFen(FenError),
+    InvalidMove { ply: usize, error: SanError },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Io(error) => write!(f, "{}", error),
+            PgnError::InvalidTag(line) => write!(f, "Invalid tag pair: {}", line),
+            PgnError::InvalidFen(error) => write!(f, "Invalid FEN tag: {}", error),
+            PgnError::InvalidMove { ply, error } => write!(f, "{} at ply {}", error, ply),
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+impl From<io::Error> for PgnError {
+    fn from(error: io::Error) -> Self {
+        PgnError::Io(error)
+    }
+}
+
+// A single game read from PGN. Comments, NAGs and variations are skipped, only the main line is played
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Reads games one at a time, so files with thousands of games never have to be held in memory
+pub struct PgnReader<R: BufRead> {
+    lines: io::Lines<R>,
+    pending: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            lines: reader.lines(),
+            pending: None,
+        }
+    }
+
+    // Collects the tag section and movetext of the next game. A game ends at its result token, at
+    // the first tag pair following its movetext, or at the end of the input. Anything after the
+    // result is left for the next game
+    fn next_game_text(&mut self) -> Result<Option<(Vec<String>, String)>, PgnError> {
+        let mut tag_lines = Vec::new();
+        let mut movetext = String::new();
+        let mut comment_depth = 0;
+        let mut variation_depth = 0;
+
+        loop {
+            let line = match self.pending.take() {
+                Some(line) => line,
+                None => match self.lines.next() {
+                    Some(line) => line?,
+                    None => break,
+                },
+            };
+            let trimmed = line.trim();
+
+            if comment_depth == 0 && trimmed.starts_with('[') {
+                if !movetext.trim().is_empty() {
+                    self.pending = Some(line);
+                    break;
+                }
+                tag_lines.push(trimmed.to_string());
+            } else if comment_depth == 0 && trimmed.starts_with('%') {
+                // Escaped line
+            } else if let Some(end) =
+                find_result_end(trimmed, &mut comment_depth, &mut variation_depth)
+            {
+                movetext.push_str(&trimmed[..end]);
+                let rest = trimmed[end..].trim();
+                if !rest.is_empty() {
+                    self.pending = Some(rest.to_string());
+                }
+                break;
+            } else {
+                movetext.push_str(trimmed);
+                movetext.push('\n');
+            }
+        }
+
+        if tag_lines.is_empty() && movetext.trim().is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some((tag_lines, movetext)))
+        }
+    }
+}
+
+// The byte offset just past the first result token of #line that is outside comments and
+// variations. #comment_depth and #variation_depth carry over from one line to the next
+fn find_result_end(
+    line: &str,
+    comment_depth: &mut i32,
+    variation_depth: &mut i32,
+) -> Option<usize> {
+    let mut token_start = None;
+
+    // A trailing space ends the last token of the line
+    for (idx, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        if *comment_depth > 0 {
+            if c == '}' {
+                *comment_depth -= 1;
+            }
+            continue;
+        }
+
+        if c.is_whitespace() || "{};()".contains(c) {
+            if let Some(start) = token_start.take() {
+                if *variation_depth == 0 && RESULTS.contains(&&line[start..idx]) {
+                    return Some(idx);
+                }
+            }
+
+            match c {
+                '{' => *comment_depth += 1,
+                // The rest of the line is a comment
+                ';' => return None,
+                '(' => *variation_depth += 1,
+                ')' => *variation_depth = (*variation_depth - 1).max(0),
+                _ => (),
+            }
+        } else if token_start.is_none() {
+            token_start = Some(idx);
+        }
+    }
+
+    None
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_game_text() {
+            Ok(Some((tag_lines, movetext))) => Some(parse_game(&tag_lines, &movetext)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+// Reads the first game of #pgn
+pub fn read_pgn(pgn: &str) -> Result<PgnGame, PgnError> {
+    match PgnReader::new(pgn.as_bytes()).next() {
+        Some(game) => game,
+        None => parse_game(&[], ""),
+    }
+}
+
+fn parse_game(tag_lines: &[String], movetext: &str) -> Result<PgnGame, PgnError> {
+    let tags = tag_lines
+        .iter()
+        .map(|line| parse_tag(line))
+        .collect::<Result<Vec<(String, String)>, PgnError>>()?;
+
+    let fen = tags
+        .iter()
+        .find(|(key, _)| key == "FEN")
+        .map(|(_, value)| value.as_str());
+    let board = match fen {
+        Some(fen) => Board::try_from_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => Board::default(),
+    };
+    let mut game = Game::new(board);
+
+    let mut result = String::from("*");
+    for (ply, token) in movetext_tokens(movetext).into_iter().enumerate() {
+        if RESULTS.contains(&token.as_str()) {
+            result = token;
+            break;
+        }
+
+        let chessmove =
+            ChessMove::from_san(&game.board, &token).map_err(|error| PgnError::InvalidMove {
+                ply: ply + 1,
+                error,
+            })?;
+        game.make_move(&chessmove);
+    }
+
+    Ok(PgnGame { tags, game, result })
+}
+
+// [Name "Value"], where the value may contain escaped quotes and backslashes
+fn parse_tag(line: &str) -> Result<(String, String), PgnError> {
+    let invalid = || PgnError::InvalidTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid)?
+        .trim();
+
+    let (name, value) = inner.split_at(inner.find(char::is_whitespace).ok_or_else(invalid)?);
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    Ok((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+// Splits movetext into SAN moves and the result token, dropping move numbers, comments, NAGs and
+// variations (which may be nested)
+fn movetext_tokens(movetext: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0;
+
+    let mut end_token = |token: &mut String, variation_depth: i32| {
+        // Move numbers may be attached to the move that follows them, as in 1.e4 or 12...Nf6
+        let digits = token.chars().take_while(char::is_ascii_digit).count();
+        let trimmed = if digits > 0 && token[digits..].starts_with('.') {
+            token[digits..].trim_start_matches('.')
+        } else {
+            token.as_str()
+        };
+
+        if variation_depth == 0 && !trimmed.is_empty() && !trimmed.starts_with('$') {
+            tokens.push(trimmed.to_string());
+        }
+        token.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                end_token(&mut token, variation_depth);
+                chars.by_ref().find(|c| *c == '}');
+            }
+            ';' => {
+                end_token(&mut token, variation_depth);
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '(' => {
+                end_token(&mut token, variation_depth);
+                variation_depth += 1;
+            }
+            ')' => {
+                end_token(&mut token, variation_depth);
+                variation_depth = (variation_depth - 1).max(0);
+            }
+            c if c.is_whitespace() => end_token(&mut token, variation_depth),
+            c => token.push(c),
+        }
+    }
+    end_token(&mut token, variation_depth);
+
+    tokens
+}
+
+// Tags missing from the Seven Tag Roster are written with their unknown value, and the result is
+// taken from the game when not given. Any other tags follow the roster
+pub fn write_pgn(game: &Game, tags: &[(String, String)]) -> String {
+    let start = game
+        .history
+        .iter()
+        .rposition(|moment| moment.chessmove.is_none())
+        .unwrap_or(0);
+    let start_fen = game.history[start].fen.as_str();
+    let start_board = Game::from_fen(start_fen).board;
+
+    let find_tag = |name: &str| {
+        tags.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let result = find_tag("Result").unwrap_or_else(|| result_token(game.status()).to_string());
+
+    let mut pgn = String::new();
+    for name in SEVEN_TAG_ROSTER.iter() {
+        let value = match *name {
+            "Result" => result.clone(),
+            "Date" => find_tag(name).unwrap_or_else(|| String::from("????.??.??")),
+            _ => find_tag(name).unwrap_or_else(|| String::from("?")),
+        };
+        pgn.push_str(&format_tag(name, &value));
+    }
+
+    if start_board.to_fen() != Board::default().to_fen() {
+        pgn.push_str(&format_tag("SetUp", "1"));
+        pgn.push_str(&format_tag("FEN", start_fen));
+    }
+
+    for (name, value) in tags.iter() {
+        if !SEVEN_TAG_ROSTER.contains(&name.as_str()) && name != "SetUp" && name != "FEN" {
+            pgn.push_str(&format_tag(name, value));
+        }
+    }
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+    let mut board = start_board;
+    for moment in game.history[start + 1..].iter() {
+        let next_board = Game::from_fen(moment.fen.as_str()).board;
+        let chessmove = moment.chessmove.unwrap();
+
+        if board.side_to_move == Color::White {
+            tokens.push(format!("{}.", board.full_moves));
+        } else if tokens.is_empty() {
+            tokens.push(format!("{}...", board.full_moves));
+        }
+        tokens.push(chessmove.to_san(&board));
+
+        board = next_board;
+    }
+    tokens.push(result);
+
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            pgn.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            pgn.push(' ');
+            line_length += 1;
+        }
+        line_length += token.len();
+        pgn.push_str(&token);
+    }
+    pgn.push('\n');
+
+    pgn
+}
+
+// A repetition or the fifty move rule only ends the game once a player claims the draw, which
+// a Game does not record, so the game is still in progress as far as its result is concerned
+pub fn result_token(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::Ongoing | GameStatus::ThreefoldRepetition | GameStatus::FiftyMoveRule => "*",
+        GameStatus::Checkmate(Color::White) => "1-0",
+        GameStatus::Checkmate(_) => "0-1",
+        GameStatus::Stalemate | GameStatus::SeventyFiveMove | GameStatus::InsufficientMaterial => {
+            "1/2-1/2"
+        }
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    format!(
+        "[{} \"{}\"]\n",
+        name,
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
@@ -0,0 +1,222 @@
+use crate::bitboard::{File, Rank, Square};
+use crate::board::Board;
+use crate::chessmove::ChessMove;
+use crate::movegen::MoveGen;
+use crate::piece::Pieces;
+use crate::Game;
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SanError {
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::InvalidSyntax(san) => write!(f, "Invalid SAN: {}", san),
+            SanError::IllegalMove(san) => write!(f, "Illegal move: {}", san),
+            SanError::AmbiguousMove(san) => write!(f, "Ambiguous move: {}", san),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+// Standard Algebraic Notation. Lives here rather than in the chessmove crate, because both
+// directions need the legal moves of the position, and the check suffix needs the move played out.
+// Checkers and pins are recalculated, so a board straight from Board::from_fen can be passed
+pub trait San: Sized {
+    fn to_san(&self, board: &Board) -> String;
+    fn from_san(board: &Board, san: &str) -> Result<Self, SanError>;
+}
+
+impl San for ChessMove {
+    fn to_san(&self, board: &Board) -> String {
+        let board = &Game::new(*board).board;
+        let moving_piece = board.get_piece_at(self.from);
+        let legal_moves = MoveGen::gen_legal_moves(board);
+
+        let mut san = if is_castle(moving_piece, self) {
+            if self.to.file() == File::G {
+                String::from("O-O")
+            } else {
+                String::from("O-O-O")
+            }
+        } else {
+            let is_capture = board.get_piece_at(self.to) != Pieces::Empty
+                || (is_pawn(moving_piece) && self.to.bitboard() == board.en_passant);
+            let mut san = String::new();
+
+            if is_pawn(moving_piece) {
+                if is_capture {
+                    san.push_str(&file_notation(self.from));
+                }
+            } else {
+                san.push_str(&piece_letter(moving_piece));
+                san.push_str(&disambiguation(board, self, &legal_moves));
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&Board::square_to_notation(self.to));
+
+            if let Some(promotion) = self.promotion {
+                san.push('=');
+                san.push_str(&piece_letter(promotion));
+            }
+
+            san
+        };
+
+        let mut game = Game::new(*board);
+        game.set_record_history(false);
+        game.make_move(self);
+        if game.board.checkers.is_not_empty() {
+            if MoveGen::gen_legal_moves(&game.board).is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    fn from_san(board: &Board, san: &str) -> Result<Self, SanError> {
+        let board = &Game::new(*board).board;
+        let invalid = || SanError::InvalidSyntax(san.to_string());
+        let trimmed = san.trim().trim_end_matches(|c| "+#!?".contains(c));
+        let legal_moves = MoveGen::gen_legal_moves(board);
+
+        let candidates: Vec<ChessMove> = match trimmed {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let file = if trimmed.len() == 3 { File::G } else { File::C };
+                legal_moves
+                    .into_iter()
+                    .filter(|chessmove| {
+                        is_castle(board.get_piece_at(chessmove.from), chessmove)
+                            && chessmove.to.file() == file
+                    })
+                    .collect()
+            }
+            _ => {
+                let (body, promotion) = match trimmed.find('=') {
+                    Some(idx) => (&trimmed[..idx], Some(&trimmed[idx + 1..])),
+                    None => match trimmed.char_indices().last() {
+                        Some((idx, c)) if "QRBN".contains(c) && idx >= 2 => {
+                            (&trimmed[..idx], Some(&trimmed[idx..]))
+                        }
+                        _ => (trimmed, None),
+                    },
+                };
+
+                if let Some(promotion) = promotion {
+                    if promotion.len() != 1 || !"QRBN".contains(promotion) {
+                        return Err(invalid());
+                    }
+                }
+
+                let (piece, body) = match body.chars().next() {
+                    Some(c) if "KQRBN".contains(c) => (c.to_string(), &body[1..]),
+                    _ => (String::from("P"), body),
+                };
+
+                let body = body.replace('x', "");
+                if body.len() < 2 || !body.is_ascii() {
+                    return Err(invalid());
+                }
+                let (disambiguation, destination) = body.split_at(body.len() - 2);
+                let to = destination.parse::<Square>().map_err(|_| invalid())?;
+                if disambiguation.len() > 2 {
+                    return Err(invalid());
+                }
+
+                let mut from_file = None;
+                let mut from_rank = None;
+                for c in disambiguation.chars() {
+                    match c {
+                        'a'..='h' => from_file = File::from_char(c),
+                        '1'..='8' => from_rank = Rank::from_char(c),
+                        _ => return Err(invalid()),
+                    }
+                }
+
+                legal_moves
+                    .into_iter()
+                    .filter(|chessmove| {
+                        let moving_piece = board.get_piece_at(chessmove.from);
+
+                        chessmove.to == to
+                            && piece_letter(moving_piece) == piece
+                            && !is_castle(moving_piece, chessmove)
+                            && chessmove.promotion.map(piece_letter).as_deref() == promotion
+                            && from_file.is_none_or(|file| chessmove.from.file() == file)
+                            && from_rank.is_none_or(|rank| chessmove.from.rank() == rank)
+                    })
+                    .collect()
+            }
+        };
+
+        match candidates.len() {
+            0 => Err(SanError::IllegalMove(san.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
+
+fn is_pawn(piece: Pieces) -> bool {
+    matches!(piece, Pieces::WPawn | Pieces::BPawn)
+}
+
+fn is_castle(piece: Pieces, chessmove: &ChessMove) -> bool {
+    matches!(piece, Pieces::WKing | Pieces::BKing)
+        && (chessmove.from.file().index() as i8 - chessmove.to.file().index() as i8).abs() == 2
+}
+
+fn piece_letter(piece: Pieces) -> String {
+    piece.to_string().to_uppercase()
+}
+
+fn file_notation(square: Square) -> String {
+    square.file().to_string()
+}
+
+// The file of the moving piece is preferred, then its rank, and both only when neither is unique
+fn disambiguation(board: &Board, chessmove: &ChessMove, legal_moves: &[ChessMove]) -> String {
+    let moving_piece = board.get_piece_at(chessmove.from);
+    let others: Vec<Square> = legal_moves
+        .iter()
+        .filter(|other| {
+            other.to == chessmove.to
+                && other.from != chessmove.from
+                && board.get_piece_at(other.from) == moving_piece
+        })
+        .map(|other| other.from)
+        .collect();
+
+    if others.is_empty() {
+        return String::new();
+    }
+
+    let from = Board::square_to_notation(chessmove.from);
+    if others
+        .iter()
+        .all(|other| other.file() != chessmove.from.file())
+    {
+        from[..1].to_string()
+    } else if others
+        .iter()
+        .all(|other| other.rank() != chessmove.from.rank())
+    {
+        from[1..].to_string()
+    } else {
+        from
+    }
+}
@@ -0,0 +1,45 @@
+use crate::board::Board;
+use crate::constants::*;
+use crate::piece::Color;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameStatus {
+    Ongoing,
+    // Holds the color of the winning side
+    Checkmate(Color),
+    Stalemate,
+    // Claimable draws
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    // Automatic draws
+    SeventyFiveMove,
+    InsufficientMaterial,
+}
+
+impl GameStatus {
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+}
+
+// Neither side can deliver mate with any sequence of legal moves: bare kings, a single minor piece,
+// or only bishops that all stand on squares of the same color
+pub fn is_insufficient_material(board: &Board) -> bool {
+    let heavy_pieces_and_pawns = board.combined_bbs[ALL_PAWNS_BB]
+        | board.combined_bbs[ALL_ROOKS_BB]
+        | board.combined_bbs[ALL_QUEENS_BB];
+
+    if heavy_pieces_and_pawns.is_not_empty() {
+        return false;
+    }
+
+    let knights = board.combined_bbs[ALL_KNIGHTS_BB];
+    let bishops = board.combined_bbs[ALL_BISHOPS_BB];
+
+    if (knights | bishops).popcnt() <= 1 {
+        return true;
+    }
+
+    knights.is_empty()
+        && ((bishops & LIGHT_SQUARES).is_empty() || (bishops & DARK_SQUARES).is_empty())
+}
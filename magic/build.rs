@@ -0,0 +1,146 @@
+// Generates the lookup tables in src/tables.rs at build time, so a clean checkout builds without
+// running anything first
+extern crate bitboard;
+use crate::bitboard::*;
+
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// (rank, file) steps, in the same order as the direction constants in src/tables.rs
+const DIRECTIONS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const EMPTY: BitBoard = BitBoard(0);
+
+// Indexed by color, white first
+const PAWN_OFFSETS: [[(i8, i8); 2]; 2] = [[(1, -1), (1, 1)], [(-1, -1), (-1, 1)]];
+
+fn square(idx: usize) -> BitBoard {
+    BitBoard(1 << idx)
+}
+
+fn offset(idx: usize, (rank_step, file_step): (i8, i8)) -> Option<usize> {
+    let rank = (idx / 8) as i8 + rank_step;
+    let file = (idx % 8) as i8 + file_step;
+
+    if (0..8).contains(&rank) && (0..8).contains(&file) {
+        Some((rank * 8 + file) as usize)
+    } else {
+        None
+    }
+}
+
+fn ray(idx: usize, direction: (i8, i8)) -> BitBoard {
+    let mut ray = EMPTY;
+    let mut current = idx;
+    while let Some(next) = offset(current, direction) {
+        ray |= square(next);
+        current = next;
+    }
+
+    ray
+}
+
+fn leaper_attacks(idx: usize, offsets: &[(i8, i8)]) -> BitBoard {
+    offsets
+        .iter()
+        .filter_map(|step| offset(idx, *step))
+        .fold(EMPTY, |attacks, target| attacks | square(target))
+}
+
+fn main() {
+    let rays: Vec<Vec<BitBoard>> = DIRECTIONS
+        .iter()
+        .map(|direction| (0..64).map(|idx| ray(idx, *direction)).collect())
+        .collect();
+
+    let mut between = vec![vec![EMPTY; 64]; 64];
+    let mut line = vec![vec![EMPTY; 64]; 64];
+    for from in 0..64 {
+        for to in 0..64 {
+            if let Some(direction) = (0..8).find(|dir| (rays[*dir][from] & square(to)).0 != 0) {
+                let opposite = (direction + 4) % 8;
+                between[from][to] = rays[direction][from] & rays[opposite][to];
+                line[from][to] = rays[direction][from] | rays[opposite][from] | square(from);
+            }
+        }
+    }
+
+    let knight_attacks: Vec<BitBoard> = (0..64)
+        .map(|idx| leaper_attacks(idx, &KNIGHT_OFFSETS))
+        .collect();
+    let king_attacks: Vec<BitBoard> = (0..64)
+        .map(|idx| leaper_attacks(idx, &DIRECTIONS))
+        .collect();
+    let pawn_attacks: Vec<Vec<BitBoard>> = PAWN_OFFSETS
+        .iter()
+        .map(|offsets| (0..64).map(|idx| leaper_attacks(idx, offsets)).collect())
+        .collect();
+
+    let out_file = Path::new(&env::var("OUT_DIR").unwrap()).join("tables.rs");
+    let mut f = BufWriter::new(File::create(out_file).unwrap());
+
+    write_table_2d(&mut f, "BETWEEN", &between);
+    write_table_2d(&mut f, "LINE", &line);
+    write_table_2d(&mut f, "RAYS", &rays);
+    write_table(&mut f, "KNIGHT_ATTACKS", &knight_attacks);
+    write_table(&mut f, "KING_ATTACKS", &king_attacks);
+    write_table_2d(&mut f, "PAWN_ATTACKS", &pawn_attacks);
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+fn format_entries(entries: &[BitBoard]) -> String {
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|bb| format!("BitBoard({:#x})", bb.0))
+        .collect();
+    format!("[{}]", entries.join(", "))
+}
+
+fn write_table(f: &mut impl Write, name: &str, table: &[BitBoard]) {
+    writeln!(
+        f,
+        "pub static {}: [BitBoard; {}] = {};",
+        name,
+        table.len(),
+        format_entries(table)
+    )
+    .unwrap();
+}
+
+fn write_table_2d(f: &mut impl Write, name: &str, table: &[Vec<BitBoard>]) {
+    writeln!(
+        f,
+        "pub static {}: [[BitBoard; {}]; {}] = [",
+        name,
+        table[0].len(),
+        table.len()
+    )
+    .unwrap();
+    for row in table.iter() {
+        writeln!(f, "    {},", format_entries(row)).unwrap();
+    }
+    writeln!(f, "];").unwrap();
+}
@@ -0,0 +1,26 @@
+// Prints the magic numbers in src/magic_numbers.rs. Finding them takes a few seconds in release
+// mode, too long to repeat in build.rs, so they are checked in instead:
+// cargo run --release -p magic --example find_magics > magic/src/magic_numbers.rs
+extern crate constants;
+use crate::constants::*;
+
+extern crate magic;
+use crate::magic::magics::*;
+
+fn main() {
+    let mut rng = MagicRng::new(0x2545_f491_4f6c_dd1d);
+
+    println!("// Generated by examples/find_magics.rs");
+    for (name, directions) in [
+        ("ROOK_MAGICS", ROOK_DIRECTIONS),
+        ("BISHOP_MAGICS", BISHOP_DIRECTIONS),
+    ]
+    .iter()
+    {
+        println!("pub const {}: [u64; 64] = [", name);
+        for square in SQUARES.iter() {
+            println!("    {:#018x},", find_magic(*square, directions, &mut rng));
+        }
+        println!("];");
+    }
+}
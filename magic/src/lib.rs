@@ -1,6 +1,236 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
-pub mod between_generated;
+pub mod magic_numbers;
+pub mod magics;
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+mod pext;
+pub mod tables;
 extern crate bitboard;
 use crate::bitboard::*;
+extern crate constants;
+use crate::constants::*;
+
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+pub use magics::PextBackend;
+pub use magics::{
+    bishop_attacks, queen_attacks, rook_attacks, AttackTables, Backend, MagicBackend,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::magics::*;
+    use crate::tables::*;
+
+    // Sparse pseudo random occupancies, spread over the whole board
+    fn occupancies() -> Vec<BitBoard> {
+        let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+        (0..200)
+            .map(|_| {
+                let mut next = || {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    seed
+                };
+                BitBoard(next() & next())
+            })
+            .collect()
+    }
+
+    mod rook_attacks {
+        use super::*;
+
+        #[test]
+        fn it_matches_walking_the_rays() {
+            for square in SQUARES.iter() {
+                for occupied in occupancies() {
+                    assert_eq!(
+                        rook_attacks(*square, occupied),
+                        slider_attacks(*square, occupied, &ROOK_DIRECTIONS)
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn it_stops_at_the_first_blocker() {
+            let occupied = D1_SQUARE | D6_SQUARE | B4_SQUARE;
+            let expected = D2_SQUARE
+                | D3_SQUARE
+                | D1_SQUARE
+                | D5_SQUARE
+                | D6_SQUARE
+                | C4_SQUARE
+                | B4_SQUARE
+                | E4_SQUARE
+                | F4_SQUARE
+                | G4_SQUARE
+                | H4_SQUARE;
+            assert_eq!(rook_attacks(D4_SQUARE, occupied), expected);
+        }
+    }
+
+    mod bishop_attacks {
+        use super::*;
+
+        #[test]
+        fn it_matches_walking_the_rays() {
+            for square in SQUARES.iter() {
+                for occupied in occupancies() {
+                    assert_eq!(
+                        bishop_attacks(*square, occupied),
+                        slider_attacks(*square, occupied, &BISHOP_DIRECTIONS)
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+    mod pext {
+        use super::*;
+
+        #[test]
+        fn it_matches_the_magic_backend() {
+            // Nothing to compare against on a CPU without BMI2
+            let pext = match PextBackend::new() {
+                Some(backend) => AttackTables::with_backend(backend),
+                None => return,
+            };
+            let magic = AttackTables::<MagicBackend>::new();
+
+            for square in SQUARES.iter() {
+                for occupied in occupancies() {
+                    assert_eq!(
+                        pext.rook_attacks(*square, occupied),
+                        magic.rook_attacks(*square, occupied)
+                    );
+                    assert_eq!(
+                        pext.bishop_attacks(*square, occupied),
+                        magic.bishop_attacks(*square, occupied)
+                    );
+                }
+            }
+        }
+    }
+
+    mod relevant_occupancy {
+        use super::*;
+
+        #[test]
+        fn it_leaves_out_the_edges() {
+            assert_eq!(relevant_occupancy(A1_SQUARE, &ROOK_DIRECTIONS).popcnt(), 12);
+            assert_eq!(relevant_occupancy(D4_SQUARE, &ROOK_DIRECTIONS).popcnt(), 10);
+            assert_eq!(
+                relevant_occupancy(D4_SQUARE, &BISHOP_DIRECTIONS).popcnt(),
+                9
+            );
+            assert_eq!(
+                relevant_occupancy(A1_SQUARE, &BISHOP_DIRECTIONS).popcnt(),
+                6
+            );
+        }
+    }
+
+    mod find_magic {
+        use super::*;
+
+        #[test]
+        fn it_finds_a_magic_without_harmful_collisions() {
+            let mut rng = MagicRng::new(1);
+            let magic = find_magic(E4_SQUARE, &BISHOP_DIRECTIONS, &mut rng);
+            let mask = relevant_occupancy(E4_SQUARE, &BISHOP_DIRECTIONS);
+            let shift = 64 - mask.popcnt();
+
+            let mut slots = std::collections::HashMap::new();
+            for occupied in occupancies() {
+                let occupied = occupied & mask;
+                let idx = occupied.0.wrapping_mul(magic) >> shift;
+                let attacks = slider_attacks(E4_SQUARE, occupied, &BISHOP_DIRECTIONS);
+                assert_eq!(*slots.entry(idx).or_insert(attacks), attacks);
+            }
+        }
+    }
+
+    mod between {
+        use super::*;
+
+        #[test]
+        fn it_holds_the_squares_strictly_between() {
+            assert_eq!(
+                BETWEEN[A1_SQUARE.index()][D4_SQUARE.index()],
+                B2_SQUARE | C3_SQUARE
+            );
+            assert_eq!(
+                BETWEEN[H8_SQUARE.index()][H5_SQUARE.index()],
+                H7_SQUARE | H6_SQUARE
+            );
+            assert_eq!(BETWEEN[A1_SQUARE.index()][B2_SQUARE.index()], EMPTY);
+            assert_eq!(BETWEEN[A1_SQUARE.index()][B3_SQUARE.index()], EMPTY);
+        }
+    }
+
+    mod line {
+        use super::*;
+
+        #[test]
+        fn it_runs_from_edge_to_edge() {
+            assert_eq!(
+                LINE[C3_SQUARE.index()][E5_SQUARE.index()],
+                SQUARES
+                    .iter()
+                    .step_by(9)
+                    .fold(EMPTY, |line, square| line | *square)
+            );
+            assert_eq!(LINE[E2_SQUARE.index()][E7_SQUARE.index()], E_FILE);
+            assert_eq!(LINE[E2_SQUARE.index()][F4_SQUARE.index()], EMPTY);
+        }
+    }
+
+    mod rays {
+        use super::*;
+
+        #[test]
+        fn it_matches_walking_the_rays() {
+            let directions = [
+                (NORTH, (1, 0)),
+                (NORTH_EAST, (1, 1)),
+                (EAST, (0, 1)),
+                (SOUTH_EAST, (-1, 1)),
+                (SOUTH, (-1, 0)),
+                (SOUTH_WEST, (-1, -1)),
+                (WEST, (0, -1)),
+                (NORTH_WEST, (1, -1)),
+            ];
+            for (direction, step) in directions.iter() {
+                for square in SQUARES.iter() {
+                    assert_eq!(
+                        RAYS[*direction][square.index()],
+                        slider_attacks(*square, EMPTY, &[*step])
+                    );
+                }
+            }
+        }
+    }
+
+    mod leaper_attacks {
+        use super::*;
+
+        #[test]
+        fn it_clips_at_the_edges() {
+            assert_eq!(KNIGHT_ATTACKS[A1_SQUARE.index()], B3_SQUARE | C2_SQUARE);
+            assert_eq!(KNIGHT_ATTACKS[E4_SQUARE.index()].popcnt(), 8);
+            assert_eq!(
+                KING_ATTACKS[H8_SQUARE.index()],
+                G8_SQUARE | G7_SQUARE | H7_SQUARE
+            );
+            assert_eq!(PAWN_ATTACKS[WHITE][A2_SQUARE.index()], B3_SQUARE);
+            assert_eq!(
+                PAWN_ATTACKS[BLACK][E7_SQUARE.index()],
+                D6_SQUARE | F6_SQUARE
+            );
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use crate::bitboard::*;
+use crate::constants::*;
+use crate::magic_numbers::*;
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+use crate::pext;
+
+use std::sync::OnceLock;
+
+pub const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+pub const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+// Enough room for every rook and bishop occupancy without sharing slots between squares
+const TABLE_SIZE: usize = 102_400 + 5_248;
+
+// How the occupancy around a square is turned into an index into the attack table
+pub trait Backend {
+    fn index(&self, magic: &Magic, occupied: BitBoard) -> usize;
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct MagicBackend;
+
+impl Backend for MagicBackend {
+    #[inline]
+    fn index(&self, magic: &Magic, occupied: BitBoard) -> usize {
+        magic.index(occupied)
+    }
+}
+
+// Parallel bit extraction from BMI2, compiled in with the `pext` feature. Only handed out by #new
+// on a CPU that has it, so holding one is proof that the instruction can be used
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+#[derive(Clone, Copy)]
+pub struct PextBackend(());
+
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+impl PextBackend {
+    pub fn new() -> Option<PextBackend> {
+        if pext::is_supported() {
+            Some(PextBackend(()))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+impl Backend for PextBackend {
+    #[inline]
+    fn index(&self, magic: &Magic, occupied: BitBoard) -> usize {
+        // Safe, as there is no PextBackend without BMI2
+        magic.offset + unsafe { pext::pext(occupied.0, magic.mask.0) }
+    }
+}
+
+// Everything needed to turn the occupancy around a square into an index into the attack table
+#[derive(Clone, Copy, Default)]
+pub struct Magic {
+    pub mask: BitBoard,
+    pub magic: u64,
+    pub shift: u32,
+    pub offset: usize,
+}
+
+impl Magic {
+    pub fn index(&self, occupied: BitBoard) -> usize {
+        let relevant = (occupied & self.mask).0;
+        self.offset + (relevant.wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+pub struct AttackTables<B = MagicBackend> {
+    rooks: [Magic; 64],
+    bishops: [Magic; 64],
+    attacks: Vec<BitBoard>,
+    backend: B,
+}
+
+impl<B: Backend + Default> AttackTables<B> {
+    pub fn new() -> AttackTables<B> {
+        AttackTables::with_backend(B::default())
+    }
+}
+
+impl<B: Backend> AttackTables<B> {
+    pub fn with_backend(backend: B) -> AttackTables<B> {
+        let mut tables = AttackTables {
+            rooks: [Magic::default(); 64],
+            bishops: [Magic::default(); 64],
+            attacks: vec![EMPTY; TABLE_SIZE],
+            backend,
+        };
+
+        let mut offset = 0;
+        for (idx, square) in SQUARES.iter().enumerate() {
+            tables.rooks[idx] =
+                tables.fill_attacks(&mut offset, *square, ROOK_MAGICS[idx], &ROOK_DIRECTIONS);
+        }
+        for (idx, square) in SQUARES.iter().enumerate() {
+            tables.bishops[idx] =
+                tables.fill_attacks(&mut offset, *square, BISHOP_MAGICS[idx], &BISHOP_DIRECTIONS);
+        }
+
+        tables
+    }
+
+    pub fn rook_attacks(&self, square: BitBoard, occupied: BitBoard) -> BitBoard {
+        self.attacks[self.backend.index(&self.rooks[square.index()], occupied)]
+    }
+
+    pub fn bishop_attacks(&self, square: BitBoard, occupied: BitBoard) -> BitBoard {
+        self.attacks[self.backend.index(&self.bishops[square.index()], occupied)]
+    }
+
+    fn fill_attacks(
+        &mut self,
+        offset: &mut usize,
+        square: BitBoard,
+        magic: u64,
+        directions: &[(i8, i8)],
+    ) -> Magic {
+        let mask = relevant_occupancy(square, directions);
+        let magic = Magic {
+            mask,
+            magic,
+            shift: 64 - mask.popcnt(),
+            offset: *offset,
+        };
+
+        for occupied in subsets(mask) {
+            let idx = self.backend.index(&magic, occupied);
+            self.attacks[idx] = slider_attacks(square, occupied, directions);
+        }
+        *offset += 1 << mask.popcnt();
+
+        magic
+    }
+}
+
+impl<B: Backend + Default> Default for AttackTables<B> {
+    fn default() -> AttackTables<B> {
+        AttackTables::new()
+    }
+}
+
+// The tables of whichever backend the CPU running the program supports
+enum Tables {
+    Magic(AttackTables<MagicBackend>),
+    #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+    Pext(AttackTables<PextBackend>),
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+// The backend is picked and the tables are filled on first use, the magic numbers themselves are
+// found ahead of time by examples/find_magics.rs
+fn tables() -> &'static Tables {
+    TABLES.get_or_init(|| {
+        #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+        {
+            if let Some(backend) = PextBackend::new() {
+                return Tables::Pext(AttackTables::with_backend(backend));
+            }
+        }
+        Tables::Magic(AttackTables::new())
+    })
+}
+
+pub fn rook_attacks(square: BitBoard, occupied: BitBoard) -> BitBoard {
+    match tables() {
+        Tables::Magic(tables) => tables.rook_attacks(square, occupied),
+        #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+        Tables::Pext(tables) => tables.rook_attacks(square, occupied),
+    }
+}
+
+pub fn bishop_attacks(square: BitBoard, occupied: BitBoard) -> BitBoard {
+    match tables() {
+        Tables::Magic(tables) => tables.bishop_attacks(square, occupied),
+        #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+        Tables::Pext(tables) => tables.bishop_attacks(square, occupied),
+    }
+}
+
+pub fn queen_attacks(square: BitBoard, occupied: BitBoard) -> BitBoard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+// Walks each direction from #square until the edge of the board or the first occupied square,
+// which is included in the attacks
+pub fn slider_attacks(square: BitBoard, occupied: BitBoard, directions: &[(i8, i8)]) -> BitBoard {
+    let mut attacks = EMPTY;
+
+    for (rank_step, file_step) in directions.iter() {
+        let mut rank = (square.index() / 8) as i8 + rank_step;
+        let mut file = (square.index() % 8) as i8 + file_step;
+
+        while (0..8).contains(&rank) && (0..8).contains(&file) {
+            let target = SQUARES[(rank * 8 + file) as usize];
+            attacks |= target;
+            if (target & occupied).is_not_empty() {
+                break;
+            }
+            rank += rank_step;
+            file += file_step;
+        }
+    }
+
+    attacks
+}
+
+// The squares whose occupancy changes the attacks from #square. The last square of every ray is
+// left out, as it is attacked whether or not something stands on it
+pub fn relevant_occupancy(square: BitBoard, directions: &[(i8, i8)]) -> BitBoard {
+    let on_board = |rank: i8, file: i8| (0..8).contains(&rank) && (0..8).contains(&file);
+    let mut mask = EMPTY;
+
+    for (rank_step, file_step) in directions.iter() {
+        let mut rank = (square.index() / 8) as i8 + rank_step;
+        let mut file = (square.index() % 8) as i8 + file_step;
+
+        while on_board(rank + rank_step, file + file_step) {
+            mask |= SQUARES[(rank * 8 + file) as usize];
+            rank += rank_step;
+            file += file_step;
+        }
+    }
+
+    mask
+}
+
+// Every subset of #mask, using the carry-rippler trick
+fn subsets(mask: BitBoard) -> Vec<BitBoard> {
+    let mut subsets = Vec::with_capacity(1 << mask.popcnt());
+    let mut subset = 0u64;
+
+    loop {
+        subsets.push(BitBoard(subset));
+        subset = subset.wrapping_sub(mask.0) & mask.0;
+        if subset == 0 {
+            break;
+        }
+    }
+
+    subsets
+}
+
+// Small xorshift generator, so the search for magics does not need an external crate and always
+// finds the same numbers
+pub struct MagicRng(u64);
+
+impl MagicRng {
+    pub fn new(seed: u64) -> MagicRng {
+        MagicRng(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(2_685_821_657_736_338_717)
+    }
+
+    // Magics with few bits set are found much faster
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+// Tries random candidates until one maps every occupancy of the relevant squares to a slot that
+// only holds that occupancy's attacks, or attacks equal to them
+pub fn find_magic(square: BitBoard, directions: &[(i8, i8)], rng: &mut MagicRng) -> u64 {
+    let mask = relevant_occupancy(square, directions);
+    let bits = mask.popcnt();
+    let occupancies = subsets(mask);
+    let attacks: Vec<BitBoard> = occupancies
+        .iter()
+        .map(|occupied| slider_attacks(square, *occupied, directions))
+        .collect();
+
+    let mut used = vec![None; 1 << bits];
+    loop {
+        let magic = rng.sparse();
+        if (mask.0.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        used.iter_mut().for_each(|slot| *slot = None);
+        let is_magic = occupancies
+            .iter()
+            .zip(attacks.iter())
+            .all(|(occupied, attack)| {
+                let idx = (occupied.0.wrapping_mul(magic) >> (64 - bits)) as usize;
+                match used[idx] {
+                    None => {
+                        used[idx] = Some(*attack);
+                        true
+                    }
+                    Some(existing) => existing == *attack,
+                }
+            });
+
+        if is_magic {
+            return magic;
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use std::arch::x86_64::_pext_u64;
+use std::sync::OnceLock;
+
+static SUPPORTED: OnceLock<bool> = OnceLock::new();
+
+// Whether the CPU running the program has BMI2. Detected on the first call and remembered after
+pub fn is_supported() -> bool {
+    *SUPPORTED.get_or_init(|| is_x86_feature_detected!("bmi2"))
+}
+
+// Packs the bits of #value selected by #mask into the low bits of the result, so every subset of
+// the mask gets its own index without needing a magic number.
+// Must only be called after #is_supported returned true
+#[target_feature(enable = "bmi2")]
+pub unsafe fn pext(value: u64, mask: u64) -> usize {
+    _pext_u64(value, mask) as usize
+}
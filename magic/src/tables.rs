@@ -0,0 +1,18 @@
+use crate::bitboard::BitBoard;
+
+// Directions indexing #RAYS, clockwise from north (towards rank 8)
+pub const NORTH: usize = 0;
+pub const NORTH_EAST: usize = 1;
+pub const EAST: usize = 2;
+pub const SOUTH_EAST: usize = 3;
+pub const SOUTH: usize = 4;
+pub const SOUTH_WEST: usize = 5;
+pub const WEST: usize = 6;
+pub const NORTH_WEST: usize = 7;
+
+// Generated by build.rs:
+// - BETWEEN[from][to]: the squares strictly between two squares on a shared line, or EMPTY
+// - LINE[from][to]: the whole line through two squares, edge to edge, or EMPTY
+// - RAYS[direction][from]: the squares from a square to the edge, not including the square itself
+// - KNIGHT_ATTACKS, KING_ATTACKS[from] and PAWN_ATTACKS[color][from]
+include!(concat!(env!("OUT_DIR"), "/tables.rs"));
@@ -0,0 +1,70 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use bitboard::*;
+use board::Board;
+use constants::*;
+use movegen::{init_board_from_fen, MoveGen};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+// The directional fills movegen used before the magic tables, kept here for comparison
+fn kogge_stone_rook_moves(board: &Board, squares: BitBoard) -> BitBoard {
+    MoveGen::south_attacks(board, squares, EMPTY)
+        | MoveGen::north_attacks(board, squares, EMPTY)
+        | MoveGen::east_attacks(board, squares, EMPTY)
+        | MoveGen::west_attacks(board, squares, EMPTY)
+}
+
+fn kogge_stone_bishop_moves(board: &Board, squares: BitBoard) -> BitBoard {
+    MoveGen::south_east_attacks(board, squares, EMPTY)
+        | MoveGen::north_east_attacks(board, squares, EMPTY)
+        | MoveGen::north_west_attacks(board, squares, EMPTY)
+        | MoveGen::south_west_attacks(board, squares, EMPTY)
+}
+
+fn slider_attacks(c: &mut Criterion) {
+    let board = init_board_from_fen(KIWIPETE);
+    let mut group = c.benchmark_group("slider attacks from every square");
+
+    group.bench_function("kogge-stone rook", |b| {
+        b.iter(|| {
+            SQUARES.iter().fold(EMPTY, |acc, square| {
+                acc ^ kogge_stone_rook_moves(black_box(&board), *square)
+            })
+        })
+    });
+    group.bench_function("magic rook", |b| {
+        b.iter(|| {
+            SQUARES.iter().fold(EMPTY, |acc, square| {
+                acc ^ MoveGen::valid_rook_moves(black_box(&board), *square, EMPTY)
+            })
+        })
+    });
+    group.bench_function("kogge-stone bishop", |b| {
+        b.iter(|| {
+            SQUARES.iter().fold(EMPTY, |acc, square| {
+                acc ^ kogge_stone_bishop_moves(black_box(&board), *square)
+            })
+        })
+    });
+    group.bench_function("magic bishop", |b| {
+        b.iter(|| {
+            SQUARES.iter().fold(EMPTY, |acc, square| {
+                acc ^ MoveGen::valid_bishop_moves(black_box(&board), *square, EMPTY)
+            })
+        })
+    });
+
+    group.finish();
+}
+
+fn legal_moves(c: &mut Criterion) {
+    let board = init_board_from_fen(KIWIPETE);
+
+    c.bench_function("gen_legal_moves kiwipete", |b| {
+        b.iter(|| MoveGen::gen_legal_moves(black_box(&board)))
+    });
+}
+
+criterion_group!(benches, slider_attacks, legal_moves);
+criterion_main!(benches);
@@ -3,302 +3,401 @@ use crate::board::Board;
 extern crate bitboard;
 use crate::bitboard::*;
 extern crate chessmove;
-use crate::chessmove::ChessMove;
+use crate::chessmove::{ChessMove, Move, MoveList};
 extern crate constants;
 use crate::constants::*;
+extern crate piece;
+use crate::piece::{Color, Role};
 extern crate magic;
-use crate::magic::between_generated::*;
+use crate::magic::tables::*;
+use crate::magic::{bishop_attacks, rook_attacks};
+
+// Most valuable first, so the strongest promotion is tried first
+const PROMOTION_ROLES: [Role; 4] = [Role::Queen, Role::Rook, Role::Bishop, Role::Knight];
 
 pub fn between_bb(left: BitBoard, right: BitBoard) -> BitBoard {
     BETWEEN[left.index()][right.index()]
 }
 
+pub fn line_bb(left: BitBoard, right: BitBoard) -> BitBoard {
+    LINE[left.index()][right.index()]
+}
+
+// A piece with every square it can move to. Move generation works on these, and only splits them
+// up into a Move per destination at the end
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PieceMoves {
+    from: Square,
+    to: BitBoard,
+}
+
 pub struct MoveGen {}
 
 impl MoveGen {
-    //TODO test
+    // Every legal move, generated into a list on the stack
+    pub fn legal_moves(board: &Board) -> MoveList {
+        let mut moves = MoveList::new();
+        MoveGen::for_each_legal_piece_moves(board, |piece_moves| {
+            MoveGen::push_moves(board, piece_moves, &mut moves)
+        });
+        moves
+    }
+
+    // Every move that obeys how the pieces move, including those leaving the king in check
+    pub fn pseudo_legal_moves(board: &Board) -> MoveList {
+        let mut moves = MoveList::new();
+        MoveGen::for_each_piece_moves(board, |piece_moves| {
+            MoveGen::push_moves(board, piece_moves, &mut moves)
+        });
+        moves
+    }
+
     pub fn gen_legal_moves(board: &Board) -> Vec<ChessMove> {
-        let ksq = board.piece_bbs[board.side_to_move][KINGS_BB];
-        let valid_king_moves =
-            MoveGen::valid_king_moves(board, ksq, board.color_bbs[board.side_to_move]);
-        let safe_squares = valid_king_moves & !board.attacked_squares;
+        MoveGen::legal_moves(board)
+            .iter()
+            .map(|m| ChessMove::from(*m))
+            .collect()
+    }
+
+    fn for_each_legal_piece_moves<F: FnMut(PieceMoves)>(board: &Board, mut f: F) {
+        let ksq = board.piece_bbs[board.side_to_move.index()][KINGS_BB];
 
         // Instead of testing every move for whether it leaves the player in check,
         // first filter out king moves that place the king on an attacked square.
         // Then later filter out moves involving pinned pieces (pre-calculated) that move out of the pin,
         // And then filtler out moves that do not address an immediate check (pre-calculated).
-        let safe_king_moves = MoveGen::gen_psuedo_legal_moves(board)
-            .into_iter()
-            .map(|chessmove| {
-                if chessmove.from != ksq {
-                    return chessmove;
-                }
-                ChessMove::new(chessmove.from, safe_squares)
-            })
-            .filter(|chessmove| chessmove.to.is_not_empty())
-            .collect::<Vec<ChessMove>>();
-
+        //
         // If king is currently in check, only consider moves that:
         // a) Move the king
         // b) Capture checking piece
         // c) Block checking piece
-        if board.checkers.is_not_empty() {
+        let check_mask = if board.checkers.is_empty() {
+            BitBoard::FULL
+        } else if board.checkers.more_than_one() {
             // If king is in check by more than one piece, the only valid response is to move the king
-            if board.checkers.popcnt() > 1 {
-                if safe_squares.is_empty() {
-                    return Vec::new();
-                }
-                safe_king_moves
-                    .into_iter()
-                    .filter(|chessmove| {
-                        if chessmove.from != ksq {
-                            return false;
-                        }
-                        true
-                    })
-                    .collect::<Vec<ChessMove>>()
+            EMPTY
+        } else {
+            // Only 1 piece is attacking the king, so between_bb() returns path from attacker to king
+            // A non-king move can only be valid by capturing attacker or moving to path
+            board.checkers | between_bb(board.checkers, ksq)
+        };
+
+        MoveGen::for_each_piece_moves(board, |piece_moves| {
+            let to = if piece_moves.from.bitboard() == ksq {
+                piece_moves.to & !board.attacked_squares
+            } else if check_mask.is_empty() {
+                EMPTY
             } else {
-                // Only 1 piece is attacking the king, so between_bb() returns path from attacker to king
-                // A non-king move can only be valid by capturing attacker or moving to path
-                let attack_ray = between_bb(board.checkers, ksq);
-
-                safe_king_moves
-                    .into_iter()
-                    .map(|chessmove| {
-                        if chessmove.from == ksq {
-                            return chessmove;
-                        }
-
-                        let allowed_squares =
-                            MoveGen::filter_moves_out_of_pin(board, &chessmove, ksq);
-
-                        ChessMove::new(
-                            chessmove.from,
-                            allowed_squares & (board.checkers | attack_ray),
-                        )
-                    })
-                    .filter(|chessmove| chessmove.to.is_not_empty())
-                    .collect::<Vec<ChessMove>>()
+                let allowed_squares = MoveGen::filter_moves_out_of_pin(board, &piece_moves, ksq);
+                let (allowed_squares, en_passant) =
+                    MoveGen::split_en_passant(board, &piece_moves, allowed_squares);
+
+                (allowed_squares & check_mask) | en_passant
+            };
+
+            if to.is_not_empty() {
+                f(PieceMoves { to, ..piece_moves });
             }
-        } else {
-            // King is not in check
-            safe_king_moves
-                .into_iter()
-                .map(|chessmove| {
-                    if chessmove.from == ksq {
-                        return chessmove;
-                    }
+        });
+    }
 
-                    if (chessmove.from & board.pinned).is_empty() {
-                        return chessmove;
-                    }
+    // Captures, en passant and every promotion, the moves quiescence search plays out
+    pub fn captures(board: &Board) -> MoveList {
+        let enemies = board.color_bbs[(!board.side_to_move).index()];
+        let pawn_targets = enemies | board.en_passant | RANK_1 | RANK_8;
 
-                    let allowed_squares = MoveGen::filter_moves_out_of_pin(board, &chessmove, ksq);
+        MoveGen::legal_moves_to(board, pawn_targets, enemies)
+    }
 
-                    ChessMove::new(chessmove.from, allowed_squares)
-                })
-                .filter(|chessmove| chessmove.to.is_not_empty())
-                .collect::<Vec<ChessMove>>()
+    // Every legal move #captures leaves out, castling included
+    pub fn quiets(board: &Board) -> MoveList {
+        let enemies = board.color_bbs[(!board.side_to_move).index()];
+        let pawn_targets = enemies | board.en_passant | RANK_1 | RANK_8;
+
+        MoveGen::legal_moves_to(board, !pawn_targets, !enemies)
+    }
+
+    // Every legal reply to a check. Empty when the side to move is not in check
+    pub fn evasions(board: &Board) -> MoveList {
+        if board.checkers.is_empty() {
+            return MoveList::new();
+        }
+
+        MoveGen::legal_moves(board)
+    }
+
+    // The quiet moves that put the other king in check, either directly or by uncovering a slider
+    pub fn quiet_checks(board: &Board) -> MoveList {
+        let us = board.side_to_move;
+        let eksq = board.piece_bbs[(!us).index()][KINGS_BB];
+        let occupied = board.combined_bbs[ALL_PIECES_BB];
+        let discoverers = MoveGen::find_blockers(board, !us) & board.color_bbs[us.index()];
+
+        let bishop_checks = bishop_attacks(eksq, occupied);
+        let rook_checks = rook_attacks(eksq, occupied);
+        // Where each kind of piece would give check from, indexed by Role
+        let check_squares = [
+            PAWN_ATTACKS[(!us).index()][eksq.index()],
+            KNIGHT_ATTACKS[eksq.index()],
+            bishop_checks,
+            rook_checks,
+            bishop_checks | rook_checks,
+            EMPTY,
+        ];
+
+        let mut moves = MoveGen::quiets(board);
+        moves.retain(|m| {
+            let from = m.from_square().bitboard();
+            let to = m.to_square().bitboard();
+
+            if m.is_castle() {
+                // Only the rook can give check, so the move is played out to find where it lands
+                let mut after = *board;
+                after.make_move(&ChessMove::from(m));
+                return MoveGen::find_checkers_and_pinned_pieces(&after)
+                    .0
+                    .is_not_empty();
+            }
+
+            let discovers =
+                (from & discoverers).is_not_empty() && (to & line_bb(eksq, from)).is_empty();
+            let role = board.get_piece_at(m.from_square()).role();
+
+            discovers || role.is_some_and(|role| (to & check_squares[role.index()]).is_not_empty())
+        });
+        moves
+    }
+
+    // Legal moves restricted to #pawn_targets for pawns and #piece_targets for everything else
+    fn legal_moves_to(board: &Board, pawn_targets: BitBoard, piece_targets: BitBoard) -> MoveList {
+        let pawns = board.piece_bbs[board.side_to_move.index()][PAWNS_BB];
+        let mut moves = MoveList::new();
+
+        MoveGen::for_each_legal_piece_moves(board, |piece_moves| {
+            let targets = if (piece_moves.from.bitboard() & pawns).is_not_empty() {
+                pawn_targets
+            } else {
+                piece_targets
+            };
+            let to = piece_moves.to & targets;
+
+            if to.is_not_empty() {
+                MoveGen::push_moves(board, PieceMoves { to, ..piece_moves }, &mut moves);
+            }
+        });
+        moves
+    }
+
+    // En passant removes a piece from a square other than the destination, so neither the pin
+    // nor the check filters can vouch for it. Returns #allowed_squares without the en passant
+    // square, and separately the en passant square if playing it out leaves the king safe
+    fn split_en_passant(
+        board: &Board,
+        piece_moves: &PieceMoves,
+        allowed_squares: BitBoard,
+    ) -> (BitBoard, BitBoard) {
+        let en_passant = piece_moves.to & board.en_passant;
+        let own_pawns = board.piece_bbs[board.side_to_move.index()][PAWNS_BB];
+        if en_passant.is_empty() || (piece_moves.from.bitboard() & own_pawns).is_empty() {
+            return (allowed_squares, EMPTY);
+        }
+
+        let en_passant_square = en_passant.lsb().unwrap();
+        let captured_pawn = match board.side_to_move {
+            Color::White => en_passant_square.offset(0, -1),
+            Color::Black => en_passant_square.offset(0, 1),
+        };
+
+        let mut after = *board;
+        after.move_piece(piece_moves.from, en_passant_square);
+        if let Some(captured_pawn) = captured_pawn {
+            after.remove_piece(captured_pawn);
+        }
+
+        let ksq = after.piece_bbs[after.side_to_move.index()][KINGS_BB];
+        let checkers =
+            MoveGen::find_attackers(&after, ksq, after.color_bbs[after.side_to_move.index()]);
+
+        if checkers.is_empty() {
+            (allowed_squares & !en_passant, en_passant)
+        } else {
+            (allowed_squares & !en_passant, EMPTY)
         }
     }
 
     fn filter_moves_out_of_pin(
         board: &Board,
-        chessmove: &ChessMove,
+        piece_moves: &PieceMoves,
         king_square: BitBoard,
     ) -> BitBoard {
-        match (chessmove.from & board.pinned).is_empty() {
+        match (piece_moves.from.bitboard() & board.pinned).is_empty() {
             // Piece is not pinned
-            true => chessmove.to,
-            // Piece is pinned, so only squares on the same attack ray are valid destinations
-            // This can determined if the destination is between the king and the origin,
-            // or if the origin is between the destination and the king; the latter
-            // can be safely assumed because a sliding piece cannot skip over the attacker
-            false => chessmove.to.bits().fold(EMPTY, |acc, bit| {
-                let square = SQUARES[bit];
-                if (between_bb(king_square, square) & chessmove.from).is_not_empty()
-                    || (between_bb(king_square, chessmove.from) & square).is_not_empty()
-                {
-                    acc | square
-                } else {
-                    acc
-                }
-            }),
+            true => piece_moves.to,
+            // Piece is pinned, so only squares on the line through the king and the piece are
+            // valid destinations. The king itself blocks the part of the line behind it
+            false => piece_moves.to & line_bb(king_square, piece_moves.from.bitboard()),
         }
     }
 
-    //TODO test
-    pub fn gen_psuedo_legal_moves(board: &Board) -> Vec<ChessMove> {
-        let mut move_vec: Vec<ChessMove> = Vec::new();
-        let pawns = board.piece_bbs[board.side_to_move][PAWNS_BB];
-        let knights = board.piece_bbs[board.side_to_move][KNIGHTS_BB];
-        let bishops = board.piece_bbs[board.side_to_move][BISHOPS_BB];
-        let rooks = board.piece_bbs[board.side_to_move][ROOKS_BB];
-        let queens = board.piece_bbs[board.side_to_move][QUEENS_BB];
-        let kings = board.piece_bbs[board.side_to_move][KINGS_BB];
-        let own_side = board.color_bbs[board.side_to_move];
-
-        for bit in pawns.bits() {
-            let square = SQUARES[bit];
-            if board.side_to_move == WHITE {
-                let cm = MoveGen::valid_white_pawn_moves(board, square);
-                if cm.is_not_empty() {
-                    move_vec.push(ChessMove::new(square, cm));
-                }
-            } else {
-                let cm = MoveGen::valid_black_pawn_moves(board, square);
-                if cm.is_not_empty() {
-                    move_vec.push(ChessMove::new(square, cm));
+    // A Move for every destination, and one for every piece a pawn reaching the last rank can
+    // promote to
+    fn push_moves(board: &Board, piece_moves: PieceMoves, moves: &mut MoveList) {
+        let from = piece_moves.from;
+        let own_pieces = board.piece_bbs[board.side_to_move.index()];
+        let is_pawn = (from.bitboard() & own_pieces[PAWNS_BB]).is_not_empty();
+        let is_king = (from.bitboard() & own_pieces[KINGS_BB]).is_not_empty();
+        let enemies = board.color_bbs[(!board.side_to_move).index()];
+
+        for to in piece_moves.to.squares() {
+            let is_capture = enemies.contains(to);
+
+            if is_pawn && (to.bitboard() & (RANK_1 | RANK_8)).is_not_empty() {
+                for role in PROMOTION_ROLES.iter() {
+                    moves.push(Move::new(
+                        from,
+                        to,
+                        Move::promotion_flags(*role, is_capture),
+                    ));
                 }
+                continue;
             }
+
+            let flags = if is_capture {
+                Move::CAPTURE
+            } else if is_pawn && to.bitboard() == board.en_passant {
+                Move::EN_PASSANT
+            } else if is_pawn && from.index().abs_diff(to.index()) == 16 {
+                Move::DOUBLE_PUSH
+            } else if is_king && to.index() == from.index() + 2 {
+                Move::KING_CASTLE
+            } else if is_king && from.index() == to.index() + 2 {
+                Move::QUEEN_CASTLE
+            } else {
+                Move::QUIET
+            };
+            moves.push(Move::new(from, to, flags));
         }
+    }
+
+    pub fn gen_psuedo_legal_moves(board: &Board) -> Vec<ChessMove> {
+        MoveGen::pseudo_legal_moves(board)
+            .iter()
+            .map(|m| ChessMove::from(*m))
+            .collect()
+    }
 
-        for bit in knights.bits() {
-            let square = SQUARES[bit];
-            let cm = MoveGen::valid_knight_moves(board, square, own_side);
-            if cm.is_not_empty() {
-                move_vec.push(ChessMove::new(square, cm));
+    fn for_each_piece_moves<F: FnMut(PieceMoves)>(board: &Board, mut f: F) {
+        let side = board.side_to_move.index();
+        let own_side = board.color_bbs[side];
+        let mut push = |from: Square, to: BitBoard| {
+            if to.is_not_empty() {
+                f(PieceMoves { from, to });
             }
+        };
+        for square in board.piece_bbs[side][PAWNS_BB].squares() {
+            let to = match board.side_to_move {
+                Color::White => MoveGen::valid_white_pawn_moves(board, square.bitboard()),
+                Color::Black => MoveGen::valid_black_pawn_moves(board, square.bitboard()),
+            };
+            push(square, to);
         }
 
-        for bit in bishops.bits() {
-            let square = SQUARES[bit];
-            let cm = MoveGen::valid_bishop_moves(board, square, own_side);
-            if cm.is_not_empty() {
-                move_vec.push(ChessMove::new(square, cm));
-            }
+        for square in board.piece_bbs[side][KNIGHTS_BB].squares() {
+            push(
+                square,
+                MoveGen::valid_knight_moves(board, square.bitboard(), own_side),
+            );
         }
 
-        for bit in rooks.bits() {
-            let square = SQUARES[bit];
-            let cm = MoveGen::valid_rook_moves(board, square, own_side);
-            if cm.is_not_empty() {
-                move_vec.push(ChessMove::new(square, cm));
-            }
+        for square in board.piece_bbs[side][BISHOPS_BB].squares() {
+            push(
+                square,
+                MoveGen::valid_bishop_moves(board, square.bitboard(), own_side),
+            );
         }
 
-        for bit in queens.bits() {
-            let square = SQUARES[bit];
-            let cm = MoveGen::valid_queen_moves(board, square, own_side);
-            if cm.is_not_empty() {
-                move_vec.push(ChessMove::new(square, cm));
-            }
+        for square in board.piece_bbs[side][ROOKS_BB].squares() {
+            push(
+                square,
+                MoveGen::valid_rook_moves(board, square.bitboard(), own_side),
+            );
         }
 
-        for bit in kings.bits() {
-            let square = SQUARES[bit];
-            let cm = MoveGen::valid_king_moves(board, square, own_side);
-            if cm.is_not_empty() {
-                move_vec.push(ChessMove::new(square, cm));
-            }
+        for square in board.piece_bbs[side][QUEENS_BB].squares() {
+            push(
+                square,
+                MoveGen::valid_queen_moves(board, square.bitboard(), own_side),
+            );
         }
 
-        move_vec
+        for square in board.piece_bbs[side][KINGS_BB].squares() {
+            push(
+                square,
+                MoveGen::valid_king_moves(board, square.bitboard(), own_side),
+            );
+        }
     }
 
     pub fn valid_king_moves(board: &Board, squares: BitBoard, own_side: BitBoard) -> BitBoard {
-        let clip_h = squares & CLEAR_H_FILE;
-        let clip_a = squares & CLEAR_A_FILE;
-        let left_up = clip_a.shl(7);
-        let up = squares.shl(8);
-        let right_up = clip_h.shl(9);
-        let right = clip_h.shl(1);
-        let down_right = clip_h.shr(7);
-        let down = squares.shr(8);
-        let left_down = clip_a.shr(9);
-        let left = clip_a.shr(1);
+        let steps = squares
+            .bits()
+            .fold(EMPTY, |acc, bit| acc | KING_ATTACKS[bit]);
 
         let mut kingside_castle_move = EMPTY;
         let mut queenside_castle_move = EMPTY;
 
         if board.checkers.is_empty() {
-            if board.side_to_move == WHITE
-                && squares == E1_SQUARE
-                && ((board.castle_rights & (G1_SQUARE | C1_SQUARE)).is_not_empty())
-            {
-                if board.combined_bbs[EMPTY_SQUARES_BB]
-                    & (WHITE_KINGSIDE_CASTLE_EMPTY_SQUARES & !board.attacked_squares)
-                    == WHITE_KINGSIDE_CASTLE_EMPTY_SQUARES
+            let empty_squares = board.combined_bbs[EMPTY_SQUARES_BB];
+            if board.side_to_move == Color::White && squares == E1_SQUARE {
+                if (board.castle_rights & G1_SQUARE).is_not_empty()
+                    && empty_squares
+                        & (WHITE_KINGSIDE_CASTLE_EMPTY_SQUARES & !board.attacked_squares)
+                        == WHITE_KINGSIDE_CASTLE_EMPTY_SQUARES
                 {
                     kingside_castle_move = G1_SQUARE;
                 }
-                if board.combined_bbs[EMPTY_SQUARES_BB]
-                    & (WHITE_QUEENSIDE_CASTLE_EMPTY_SQUARES & !board.attacked_squares)
-                    == WHITE_QUEENSIDE_CASTLE_EMPTY_SQUARES
+                if (board.castle_rights & C1_SQUARE).is_not_empty()
+                    && empty_squares & WHITE_QUEENSIDE_CASTLE_EMPTY_SQUARES
+                        == WHITE_QUEENSIDE_CASTLE_EMPTY_SQUARES
+                    && (WHITE_QUEENSIDE_CASTLE_SAFE_SQUARES & board.attacked_squares).is_empty()
                 {
                     queenside_castle_move = C1_SQUARE;
                 }
-            } else if board.side_to_move == BLACK
-                && squares == E8_SQUARE
-                && ((board.castle_rights & (G8_SQUARE | C8_SQUARE)).is_not_empty())
-            {
-                if board.combined_bbs[EMPTY_SQUARES_BB]
-                    & (BLACK_KINGSIDE_CASTLE_EMPTY_SQUARES & !board.attacked_squares)
-                    == BLACK_KINGSIDE_CASTLE_EMPTY_SQUARES
+            } else if board.side_to_move == Color::Black && squares == E8_SQUARE {
+                if (board.castle_rights & G8_SQUARE).is_not_empty()
+                    && empty_squares
+                        & (BLACK_KINGSIDE_CASTLE_EMPTY_SQUARES & !board.attacked_squares)
+                        == BLACK_KINGSIDE_CASTLE_EMPTY_SQUARES
                 {
                     kingside_castle_move = G8_SQUARE;
                 }
-                if board.combined_bbs[EMPTY_SQUARES_BB]
-                    & (BLACK_QUEENSIDE_CASTLE_EMPTY_SQUARES & !board.attacked_squares)
-                    == BLACK_QUEENSIDE_CASTLE_EMPTY_SQUARES
+                if (board.castle_rights & C8_SQUARE).is_not_empty()
+                    && empty_squares & BLACK_QUEENSIDE_CASTLE_EMPTY_SQUARES
+                        == BLACK_QUEENSIDE_CASTLE_EMPTY_SQUARES
+                    && (BLACK_QUEENSIDE_CASTLE_SAFE_SQUARES & board.attacked_squares).is_empty()
                 {
                     queenside_castle_move = C8_SQUARE;
                 }
             }
         }
 
-        let moves = left_up
-            | up
-            | right_up
-            | right
-            | down_right
-            | down
-            | left_down
-            | left
-            | kingside_castle_move
-            | queenside_castle_move;
+        let moves = steps | kingside_castle_move | queenside_castle_move;
 
         moves & !own_side
     }
 
     pub fn valid_knight_moves(_board: &Board, squares: BitBoard, own_side: BitBoard) -> BitBoard {
-        let left_up_clip = CLEAR_A_FILE & CLEAR_B_FILE;
-        let up_left_clip = CLEAR_A_FILE;
-
-        let up_right_clip = CLEAR_H_FILE;
-        let right_up_clip = CLEAR_H_FILE & CLEAR_G_FILE;
-
-        let right_down_clip = CLEAR_H_FILE & CLEAR_G_FILE;
-        let down_right_clip = CLEAR_H_FILE;
-
-        let down_left_clip = CLEAR_A_FILE;
-        let left_down_clip = CLEAR_A_FILE & CLEAR_B_FILE;
-
-        let left_up = (squares & left_up_clip).shl(6);
-        let up_left = (squares & up_left_clip).shl(15);
-        let up_right = (squares & up_right_clip).shl(17);
-        let right_up = (squares & right_up_clip).shl(10);
-        let right_down = (squares & right_down_clip).shr(6);
-        let down_right = (squares & down_right_clip).shr(15);
-        let down_left = (squares & down_left_clip).shr(17);
-        let left_down = (squares & left_down_clip).shr(10);
-
-        let moves = left_up
-            | up_left
-            | up_right
-            | right_up
-            | right_down
-            | down_right
-            | down_left
-            | left_down;
+        let moves = squares
+            .bits()
+            .fold(EMPTY, |acc, bit| acc | KNIGHT_ATTACKS[bit]);
 
         moves & !own_side
     }
 
     pub fn valid_white_pawn_attacks(board: &Board, squares: BitBoard) -> BitBoard {
-        let left_attack = (squares & CLEAR_A_FILE).shl(7);
-        let right_attack = (squares & CLEAR_H_FILE).shl(9);
-        let attacks = left_attack | right_attack;
+        let attacks = squares
+            .bits()
+            .fold(EMPTY, |acc, bit| acc | PAWN_ATTACKS[WHITE][bit]);
         attacks & (board.color_bbs[BLACK] | board.en_passant)
     }
 
@@ -312,10 +411,10 @@ impl MoveGen {
     }
 
     pub fn valid_black_pawn_attacks(board: &Board, squares: BitBoard) -> BitBoard {
-        let left_attack = (squares & CLEAR_A_FILE).shr(9);
-        let right_attack = (squares & CLEAR_H_FILE).shr(7);
-        let attacks = left_attack | right_attack;
-        attacks & board.color_bbs[WHITE]
+        let attacks = squares
+            .bits()
+            .fold(EMPTY, |acc, bit| acc | PAWN_ATTACKS[BLACK][bit]);
+        attacks & (board.color_bbs[WHITE] | board.en_passant)
     }
 
     pub fn valid_black_pawn_moves(board: &Board, squares: BitBoard) -> BitBoard {
@@ -327,18 +426,21 @@ impl MoveGen {
         valid_steps | valid_attacks
     }
 
+    // Occupancy is taken from the empty squares rather than the pieces, so callers can let sliders
+    // see through a piece by marking its square empty
     pub fn valid_rook_moves(board: &Board, squares: BitBoard, own_pieces: BitBoard) -> BitBoard {
-        MoveGen::south_attacks(board, squares, own_pieces)
-            | MoveGen::north_attacks(board, squares, own_pieces)
-            | MoveGen::east_attacks(board, squares, own_pieces)
-            | MoveGen::west_attacks(board, squares, own_pieces)
+        let occupied = !board.combined_bbs[EMPTY_SQUARES_BB];
+        squares
+            .bits()
+            .fold(EMPTY, |acc, bit| acc | rook_attacks(SQUARES[bit], occupied))
+            & !own_pieces
     }
 
     pub fn valid_bishop_moves(board: &Board, squares: BitBoard, own_pieces: BitBoard) -> BitBoard {
-        MoveGen::south_east_attacks(board, squares, own_pieces)
-            | MoveGen::north_east_attacks(board, squares, own_pieces)
-            | MoveGen::north_west_attacks(board, squares, own_pieces)
-            | MoveGen::south_west_attacks(board, squares, own_pieces)
+        let occupied = !board.combined_bbs[EMPTY_SQUARES_BB];
+        squares.bits().fold(EMPTY, |acc, bit| {
+            acc | bishop_attacks(SQUARES[bit], occupied)
+        }) & !own_pieces
     }
 
     pub fn valid_queen_moves(board: &Board, squares: BitBoard, own_pieces: BitBoard) -> BitBoard {
@@ -443,11 +545,9 @@ impl MoveGen {
     }
 
     pub fn find_attack_rays(board: &Board, test_square: BitBoard, own_board: BitBoard) -> BitBoard {
-        let (other_pieces_collection, other_pieces) = match board.side_to_move {
-            WHITE => (board.piece_bbs[BLACK], board.color_bbs[BLACK]),
-            BLACK => (board.piece_bbs[WHITE], board.color_bbs[WHITE]),
-            _ => ([EMPTY; 6], EMPTY),
-        };
+        let other_side = (!board.side_to_move).index();
+        let (other_pieces_collection, other_pieces) =
+            (board.piece_bbs[other_side], board.color_bbs[other_side]);
 
         let mut bishop_attacks = MoveGen::valid_bishop_moves(board, test_square, own_board);
         let mut rook_attacks = MoveGen::valid_rook_moves(board, test_square, own_board);
@@ -475,19 +575,29 @@ impl MoveGen {
     }
 
     pub fn calculate_derived_bitboards(board: &Board) -> (BitBoard, BitBoard, BitBoard) {
-        let (checkers, pinned) = MoveGen::find_checkers_and_pinned_pieces(&board);
-        let attacked_squares = MoveGen::find_attacked_squares(&board);
+        let (checkers, pinned) = MoveGen::find_checkers_and_pinned_pieces(board);
+        let attacked_squares = MoveGen::find_attacked_squares(board);
         (checkers, pinned, attacked_squares)
     }
 
+    // Brings checkers, pins and attacked squares up to date after Board::make_move
+    pub fn update_derived_bitboards(board: &mut Board) {
+        let (checkers, pinned, attacked_squares) = MoveGen::calculate_derived_bitboards(board);
+        board.checkers = checkers;
+        board.pinned = pinned;
+        board.attacked_squares = attacked_squares;
+    }
+
     pub fn find_pinned_pieces(board: &Board) -> BitBoard {
-        let ksq = board.piece_bbs[board.side_to_move][KINGS_BB];
+        MoveGen::find_blockers(board, board.side_to_move)
+            & board.color_bbs[board.side_to_move.index()]
+    }
 
-        let other_pieces_collection = match board.side_to_move {
-            WHITE => board.piece_bbs[BLACK],
-            BLACK => board.piece_bbs[WHITE],
-            _ => panic!("Invalid side to move"),
-        };
+    // Pieces of either color that are the only piece between #color's king and a slider of the
+    // other color. #color's own blockers are pinned, the other side's can give discovered check
+    fn find_blockers(board: &Board, color: Color) -> BitBoard {
+        let ksq = board.piece_bbs[color.index()][KINGS_BB];
+        let other_pieces_collection = board.piece_bbs[(!color).index()];
 
         let bishop_like_attackers =
             other_pieces_collection[BISHOPS_BB] | other_pieces_collection[QUEENS_BB];
@@ -522,49 +632,32 @@ impl MoveGen {
         let attackers_without_blockers =
             bishop_like_attackers_without_blockers | rook_like_attackers_without_blockers;
 
-        let mut pinned = EMPTY;
+        let mut blockers = EMPTY;
         attackers_without_blockers.bits().for_each(|attacker_bit| {
             let attacker_square = SQUARES[attacker_bit];
             let king_to_attacker = between_bb(attacker_square, ksq);
-            let mut num_blockers = 0;
-            let mut blocker = EMPTY;
-            if king_to_attacker.is_not_empty() {
-                board.color_bbs[board.side_to_move]
-                    .bits()
-                    .for_each(|blocker_bit| {
-                        let test_blocker = SQUARES[blocker_bit];
-                        if (king_to_attacker & test_blocker).is_not_empty() {
-                            blocker = test_blocker;
-                            num_blockers += 1;
-                        }
-                    });
-                if num_blockers == 1 {
-                    pinned |= blocker;
-                }
+            // A piece is only pinned if it is the sole piece of either color on the ray
+            let on_ray = king_to_attacker & board.combined_bbs[ALL_PIECES_BB];
+            if on_ray.popcnt() == 1 {
+                blockers |= on_ray;
             }
         });
-        pinned
+        blockers
     }
 
     pub fn find_attackers(board: &Board, test_square: BitBoard, own_pieces: BitBoard) -> BitBoard {
         let mut attackers = EMPTY;
 
-        let other_pieces_collection: [BitBoard; 6];
-        if board.side_to_move == WHITE {
-            other_pieces_collection = board.piece_bbs[BLACK];
-        } else {
-            other_pieces_collection = board.piece_bbs[WHITE];
-        }
+        let other_pieces_collection = board.piece_bbs[(!board.side_to_move).index()];
 
         let bishop_attackers = MoveGen::valid_bishop_moves(board, test_square, own_pieces);
         let rook_attackers = MoveGen::valid_rook_moves(board, test_square, own_pieces);
         let knight_attackers = MoveGen::valid_knight_moves(board, test_square, own_pieces);
-        let pawn_attackers: BitBoard;
-        if board.side_to_move == WHITE {
-            pawn_attackers = MoveGen::valid_white_pawn_attacks(board, test_square);
+        let pawn_attackers = if board.side_to_move == Color::White {
+            MoveGen::valid_white_pawn_attacks(board, test_square)
         } else {
-            pawn_attackers = MoveGen::valid_black_pawn_attacks(board, test_square);
-        }
+            MoveGen::valid_black_pawn_attacks(board, test_square)
+        };
 
         attackers ^= bishop_attackers
             & (other_pieces_collection[BISHOPS_BB] | other_pieces_collection[QUEENS_BB]);
@@ -578,8 +671,9 @@ impl MoveGen {
 
     //TODO test
     pub fn find_checkers_and_pinned_pieces(board: &Board) -> (BitBoard, BitBoard) {
-        let ksq = board.piece_bbs[board.side_to_move][KINGS_BB];
-        let checkers = MoveGen::find_attackers(board, ksq, board.color_bbs[board.side_to_move]);
+        let ksq = board.piece_bbs[board.side_to_move.index()][KINGS_BB];
+        let checkers =
+            MoveGen::find_attackers(board, ksq, board.color_bbs[board.side_to_move.index()]);
         let pinned = MoveGen::find_pinned_pieces(board);
 
         (checkers, pinned)
@@ -590,21 +684,28 @@ impl MoveGen {
         let other_pieces = board.other_pieces();
         let mut attacked_squares = EMPTY;
 
-        attacked_squares |= MoveGen::valid_queen_moves(board, other_pieces[QUEENS_BB], EMPTY);
-        attacked_squares |= MoveGen::valid_bishop_moves(board, other_pieces[BISHOPS_BB], EMPTY);
-        attacked_squares |= MoveGen::valid_rook_moves(board, other_pieces[ROOKS_BB], EMPTY);
+        // Sliders attack straight through the king, otherwise it could step back along the
+        // attack ray and appear safe
+        let mut without_king = *board;
+        without_king.combined_bbs[EMPTY_SQUARES_BB] |=
+            board.piece_bbs[board.side_to_move.index()][KINGS_BB];
+
+        attacked_squares |=
+            MoveGen::valid_queen_moves(&without_king, other_pieces[QUEENS_BB], EMPTY);
+        attacked_squares |=
+            MoveGen::valid_bishop_moves(&without_king, other_pieces[BISHOPS_BB], EMPTY);
+        attacked_squares |= MoveGen::valid_rook_moves(&without_king, other_pieces[ROOKS_BB], EMPTY);
         attacked_squares |= MoveGen::valid_knight_moves(board, other_pieces[KNIGHTS_BB], EMPTY);
         attacked_squares |= MoveGen::valid_king_moves(board, other_pieces[KINGS_BB], EMPTY);
         let (left_pawn_attacks, right_pawn_attacks) = match board.side_to_move {
-            WHITE => (
+            Color::White => (
                 (other_pieces[PAWNS_BB] & CLEAR_A_FILE).shr(9),
                 (other_pieces[PAWNS_BB] & CLEAR_H_FILE).shr(7),
             ),
-            BLACK => (
+            Color::Black => (
                 (other_pieces[PAWNS_BB] & CLEAR_A_FILE).shl(7),
                 (other_pieces[PAWNS_BB] & CLEAR_H_FILE).shl(9),
             ),
-            _ => panic!("Invalid side to move"),
         };
         attacked_squares |= left_pawn_attacks | right_pawn_attacks;
         attacked_squares
@@ -613,22 +714,170 @@ impl MoveGen {
 
 pub fn init_board_from_fen(fen: &str) -> Board {
     let mut b = Board::from_fen(fen);
-    let (checkers, pinned, attacked_squares) = MoveGen::calculate_derived_bitboards(&b);
-    b.attacked_squares = attacked_squares;
-    b.checkers = checkers;
-    b.pinned = pinned;
+    MoveGen::update_derived_bitboards(&mut b);
     b
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use piece::Pieces;
 
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
 
+    mod move_list {
+        use super::*;
+
+        const POSITIONS: [&str; 4] = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3pPk/8/4P3/8 b - g3 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ];
+
+        #[test]
+        fn it_matches_the_flags_board_works_out() {
+            for fen in POSITIONS.iter() {
+                let b = init_board_from_fen(fen);
+                for m in MoveGen::legal_moves(&b).iter() {
+                    assert_eq!(b.encode_move(&ChessMove::from(*m)), *m, "{} in {}", m, fen);
+                }
+            }
+        }
+
+        #[test]
+        fn it_flags_special_moves() {
+            let b = init_board_from_fen("r3k2r/8/8/3pP3/8/8/P7/R3K2R w KQkq d6 0 1");
+            let moves = MoveGen::legal_moves(&b);
+            let find = |from: Square, to: Square| {
+                *moves
+                    .iter()
+                    .find(|m| m.from_square() == from && m.to_square() == to)
+                    .unwrap()
+            };
+
+            assert!(find(Square::E5, Square::D6).is_en_passant());
+            assert!(find(Square::A2, Square::A4).is_double_push());
+            assert_eq!(find(Square::E1, Square::G1).flags(), Move::KING_CASTLE);
+            assert_eq!(find(Square::E1, Square::C1).flags(), Move::QUEEN_CASTLE);
+            assert_eq!(find(Square::H1, Square::H8).flags(), Move::CAPTURE);
+            assert_eq!(find(Square::A2, Square::A3).flags(), Move::QUIET);
+        }
+    }
+
+    mod staged {
+        use super::*;
+
+        const POSITIONS: [&str; 7] = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3pPk/8/4P3/8 b - g3 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            // In check from a single piece, and from two pieces at once
+            "4k3/8/8/1b6/8/8/3PN3/4K2R w K - 0 1",
+            "4k3/8/8/8/8/5n2/8/r3K3 w - - 0 1",
+            // Discovered checks uncovered by the knight and by the king
+            "k7/8/8/8/4N3/8/K2B4/R6Q w - - 0 1",
+        ];
+
+        fn sorted(moves: &[Move]) -> Vec<u16> {
+            let mut bits: Vec<u16> = moves.iter().map(|m| m.bits()).collect();
+            bits.sort_unstable();
+            bits
+        }
+
+        fn gives_check(board: &Board, m: Move) -> bool {
+            let mut after = *board;
+            after.make_move(&ChessMove::from(m));
+            MoveGen::find_checkers_and_pinned_pieces(&after)
+                .0
+                .is_not_empty()
+        }
+
+        #[test]
+        fn it_splits_every_legal_move_into_captures_and_quiets() {
+            for fen in POSITIONS.iter() {
+                let b = init_board_from_fen(fen);
+                let mut staged: Vec<Move> = MoveGen::captures(&b).to_vec();
+                staged.extend(MoveGen::quiets(&b).iter());
+
+                assert_eq!(
+                    sorted(&staged),
+                    sorted(&MoveGen::legal_moves(&b)),
+                    "{}",
+                    fen
+                );
+                let chessmoves: Vec<ChessMove> =
+                    staged.iter().map(|m| ChessMove::from(*m)).collect();
+                assert_eq!(chessmoves.len(), MoveGen::gen_legal_moves(&b).len());
+            }
+        }
+
+        #[test]
+        fn it_only_puts_captures_and_promotions_in_captures() {
+            for fen in POSITIONS.iter() {
+                let b = init_board_from_fen(fen);
+
+                assert!(MoveGen::captures(&b)
+                    .iter()
+                    .all(|m| m.is_capture() || m.is_promotion()));
+                assert!(MoveGen::quiets(&b)
+                    .iter()
+                    .all(|m| !m.is_capture() && !m.is_promotion()));
+            }
+        }
+
+        #[test]
+        fn it_generates_evasions_only_in_check() {
+            for fen in POSITIONS.iter() {
+                let b = init_board_from_fen(fen);
+                let evasions = MoveGen::evasions(&b);
+
+                if b.checkers.is_empty() {
+                    assert!(evasions.is_empty(), "{}", fen);
+                } else {
+                    assert_eq!(
+                        sorted(&evasions),
+                        sorted(&MoveGen::legal_moves(&b)),
+                        "{}",
+                        fen
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn it_finds_every_quiet_check() {
+            for fen in POSITIONS.iter() {
+                let b = init_board_from_fen(fen);
+                let checks = MoveGen::quiet_checks(&b);
+                let expected: Vec<Move> = MoveGen::quiets(&b)
+                    .iter()
+                    .copied()
+                    .filter(|m| gives_check(&b, *m))
+                    .collect();
+
+                assert_eq!(sorted(&checks), sorted(&expected), "{}", fen);
+            }
+        }
+
+        #[test]
+        fn it_finds_discovered_checks_and_castling_checks() {
+            let b = init_board_from_fen("k7/8/8/8/4N3/8/K2B4/R6Q w - - 0 1");
+            let checks = MoveGen::quiet_checks(&b);
+            // The king uncovers the rook, every knight move uncovers the queen
+            assert!(checks.iter().any(|m| m.from_square() == Square::A2));
+            assert!(checks.iter().any(|m| m.from_square() == Square::E4));
+
+            let b = init_board_from_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1");
+            let checks = MoveGen::quiet_checks(&b);
+            assert!(checks.iter().any(|m| m.flags() == Move::KING_CASTLE));
+        }
+    }
+
     mod castling {
         use super::*;
 
@@ -641,7 +890,7 @@ mod tests {
             assert_eq!(
                 moves
                     .iter()
-                    .find(|cm| { cm.from == E1_SQUARE && ((cm.to & G1_SQUARE).is_not_empty()) }),
+                    .find(|cm| { cm.from == Square::E1 && cm.to == Square::G1 }),
                 None
             );
         }
@@ -655,12 +904,96 @@ mod tests {
             assert_eq!(
                 moves
                     .iter()
-                    .find(|cm| { cm.from == E1_SQUARE && ((cm.to & G1_SQUARE).is_not_empty()) }),
+                    .find(|cm| { cm.from == Square::E1 && cm.to == Square::G1 }),
                 None
             );
         }
     }
 
+    mod castling_queenside {
+        use super::*;
+
+        #[test]
+        fn can_castle_while_b_file_square_is_attacked() {
+            let b = init_board_from_fen(
+                "r3k2r/p1pNqpb1/bn2pnp1/3P4/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            );
+            let moves = MoveGen::gen_legal_moves(&b);
+            assert!(moves
+                .iter()
+                .any(|cm| { cm.from == Square::E8 && cm.to == Square::C8 }));
+        }
+    }
+
+    mod en_passant {
+        use super::*;
+
+        #[test]
+        fn black_can_capture_en_passant() {
+            let b = init_board_from_fen(
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            );
+            let moves = MoveGen::gen_legal_moves(&b);
+            assert!(moves
+                .iter()
+                .any(|cm| { cm.from == Square::B4 && cm.to == Square::A3 }));
+        }
+
+        #[test]
+        fn cannot_capture_en_passant_when_it_exposes_the_king() {
+            let b = init_board_from_fen("8/8/3p4/KPp4r/5p1k/1R6/4P1P1/8 w - c6 0 1");
+            let moves = MoveGen::gen_legal_moves(&b);
+            assert_eq!(
+                moves
+                    .iter()
+                    .find(|cm| { cm.from == Square::B5 && cm.to == Square::C6 }),
+                None
+            );
+        }
+    }
+
+    mod promotion {
+        use super::*;
+
+        #[test]
+        fn it_generates_every_promotion_piece() {
+            let b = init_board_from_fen("8/1P6/8/8/8/8/8/k6K w - - 0 1");
+            let moves = MoveGen::gen_legal_moves(&b);
+            let promotions: Vec<Pieces> = moves
+                .iter()
+                .filter(|cm| cm.from == Square::B7)
+                .filter_map(|cm| {
+                    assert_eq!(cm.to, Square::B8);
+                    cm.promotion
+                })
+                .collect();
+
+            assert_eq!(
+                promotions,
+                vec![
+                    Pieces::WQueen,
+                    Pieces::WRook,
+                    Pieces::WBishop,
+                    Pieces::WKnight
+                ]
+            );
+        }
+
+        #[test]
+        fn it_keeps_promotions_that_capture_a_checker() {
+            let b = init_board_from_fen("1r5K/P7/8/8/8/8/8/k7 w - - 0 1");
+            let moves = MoveGen::gen_legal_moves(&b);
+
+            assert_eq!(
+                moves
+                    .iter()
+                    .filter(|cm| cm.from == Square::A7 && cm.promotion.is_some())
+                    .count(),
+                4
+            );
+        }
+    }
+
     mod moving_into_check {
         use super::*;
 
@@ -673,7 +1006,7 @@ mod tests {
             assert_eq!(
                 moves
                     .iter()
-                    .find(|cm| { cm.from == E8_SQUARE && ((cm.to & E7_SQUARE).is_not_empty()) }),
+                    .find(|cm| { cm.from == Square::E8 && cm.to == Square::E7 }),
                 None
             );
         }
@@ -689,6 +1022,16 @@ mod tests {
     mod pinned_pieces {
         use super::*;
 
+        #[test]
+        fn it_ignores_rays_blocked_by_the_other_side() {
+            let b = init_board_from_fen(
+                "r3k2r/p1pNqpb1/bn2p1p1/3P4/1p2n3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            );
+            let pinned = MoveGen::find_pinned_pieces(&b);
+
+            assert_eq!(pinned, EMPTY);
+        }
+
         #[test]
         fn it_works() {
             let b = init_board_from_fen(
@@ -0,0 +1,171 @@
+extern crate board;
+use crate::board::Board;
+extern crate chessmove;
+use crate::chessmove::ChessMove;
+extern crate movegen;
+use crate::movegen::MoveGen;
+
+// Counts the leaf nodes of the legal move tree rooted at #board, #depth plies deep
+pub fn perft(board: &Board, depth: u8) -> u64 {
+    let mut board = *board;
+    MoveGen::update_derived_bitboards(&mut board);
+    perft_recursive(&mut board, depth)
+}
+
+// Same as #perft, but reports the node count below each legal root move separately
+pub fn divide(board: &Board, depth: u8) -> Vec<(ChessMove, u64)> {
+    let mut board = *board;
+    MoveGen::update_derived_bitboards(&mut board);
+
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    MoveGen::legal_moves(&board)
+        .iter()
+        .map(|m| {
+            let chessmove = ChessMove::from(*m);
+            let nodes = play(&mut board, &chessmove, |board| {
+                perft_recursive(board, depth - 1)
+            });
+            (chessmove, nodes)
+        })
+        .collect()
+}
+
+// Moves are played on the board itself rather than through a Game, and generated into a list on
+// the stack, so counting nodes does not allocate
+fn perft_recursive(board: &mut Board, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = MoveGen::legal_moves(board);
+
+    // Leaf nodes do not need to be played out, only counted
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves.iter().fold(0, |nodes, m| {
+        nodes
+            + play(board, &ChessMove::from(*m), |board| {
+                perft_recursive(board, depth - 1)
+            })
+    })
+}
+
+// Runs #f on #board with #chessmove played, and takes it back afterwards
+fn play<F: FnOnce(&mut Board) -> u64>(board: &mut Board, chessmove: &ChessMove, f: F) -> u64 {
+    let undo = board.make_move(chessmove);
+    MoveGen::update_derived_bitboards(board);
+    let nodes = f(board);
+    board.unmake_move(chessmove, undo);
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference node counts from https://www.chessprogramming.org/Perft_Results
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let board = Board::from_fen(fen);
+        for (idx, nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&board, idx as u8 + 1), *nodes, "depth {}", idx + 1);
+        }
+    }
+
+    mod initial_position {
+        use super::*;
+
+        #[test]
+        fn it_works() {
+            assert_perft(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                &[20, 400, 8902, 197281],
+            );
+        }
+    }
+
+    mod kiwipete {
+        use super::*;
+
+        #[test]
+        fn it_works() {
+            assert_perft(
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                &[48, 2039, 97862, 4085603],
+            );
+        }
+    }
+
+    mod position_3 {
+        use super::*;
+
+        #[test]
+        fn it_works() {
+            assert_perft(
+                "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+                &[14, 191, 2812, 43238, 674624],
+            );
+        }
+    }
+
+    mod position_4 {
+        use super::*;
+
+        #[test]
+        fn it_works() {
+            assert_perft(
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                &[6, 264, 9467, 422333],
+            );
+        }
+
+        #[test]
+        fn it_works_mirrored() {
+            assert_perft(
+                "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+                &[6, 264, 9467, 422333],
+            );
+        }
+    }
+
+    mod position_5 {
+        use super::*;
+
+        #[test]
+        fn it_works() {
+            assert_perft(
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                &[44, 1486, 62379, 2103487],
+            );
+        }
+    }
+
+    mod position_6 {
+        use super::*;
+
+        #[test]
+        fn it_works() {
+            assert_perft(
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                &[46, 2079, 89890],
+            );
+        }
+    }
+
+    mod divide {
+        use super::*;
+
+        #[test]
+        fn it_sums_to_perft() {
+            let board = Board::default();
+            let moves = divide(&board, 3);
+
+            assert_eq!(moves.len(), 20);
+            assert_eq!(moves.iter().map(|(_, nodes)| nodes).sum::<u64>(), 8902);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+extern crate board;
+use board::Board;
+extern crate perft;
+use perft::*;
+
+use std::env;
+use std::time::Instant;
+
+// Usage: perft <depth> [fen]
+// Prints the node count below every legal root move, followed by the total
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let depth = match args.first().map(|depth| depth.parse::<u8>()) {
+        Some(Ok(depth)) => depth,
+        _ => {
+            eprintln!("Usage: perft <depth> [fen]");
+            std::process::exit(1);
+        }
+    };
+
+    let board = if args.len() > 1 {
+        match Board::try_from_fen(args[1..].join(" ").as_str()) {
+            Ok(board) => board,
+            Err(error) => {
+                eprintln!("Invalid FEN: {}", error);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        Board::default()
+    };
+
+    let start = Instant::now();
+    let moves = divide(&board, depth);
+    let mut total = 0;
+
+    for (chessmove, nodes) in moves.iter() {
+        println!("{}: {}", chessmove, nodes);
+        total += nodes;
+    }
+
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time: {:?}", start.elapsed());
+}
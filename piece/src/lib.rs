@@ -1,17 +1,202 @@
+use std::error::Error;
 use std::fmt;
+use std::ops::Not;
+use std::str::FromStr;
 extern crate constants;
 use crate::constants::*;
 
-pub enum PieceType {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub const ALL: [Color; 2] = [Color::White, Color::Black];
+
+    // Matches the WHITE and BLACK indices into the color bitboards
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Color> {
+        Color::ALL.get(index).copied()
+    }
+
+    pub fn other(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+impl Not for Color {
+    type Output = Color;
+
+    fn not(self) -> Color {
+        self.other()
+    }
+}
+
+// The side to move field of a FEN
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Color::White => write!(f, "w"),
+            Color::Black => write!(f, "b"),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = PieceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "w" => Ok(Color::White),
+            "b" => Ok(Color::Black),
+            _ => Err(PieceError::InvalidColor(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
     Pawn,
     Knight,
     Bishop,
     Rook,
     Queen,
     King,
-    Empty,
 }
 
+impl Role {
+    pub const ALL: [Role; 6] = [
+        Role::Pawn,
+        Role::Knight,
+        Role::Bishop,
+        Role::Rook,
+        Role::Queen,
+        Role::King,
+    ];
+
+    // Matches PAWNS_BB through KINGS_BB, the indices into a color's piece bitboards
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Role> {
+        Role::ALL.get(index).copied()
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Role::Pawn => 'p',
+            Role::Knight => 'n',
+            Role::Bishop => 'b',
+            Role::Rook => 'r',
+            Role::Queen => 'q',
+            Role::King => 'k',
+        }
+    }
+
+    // Either case is accepted
+    pub fn from_char(c: char) -> Option<Role> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(Role::Pawn),
+            'n' => Some(Role::Knight),
+            'b' => Some(Role::Bishop),
+            'r' => Some(Role::Rook),
+            'q' => Some(Role::Queen),
+            'k' => Some(Role::King),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl FromStr for Role {
+    type Err = PieceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Role::from_char), chars.next()) {
+            (Some(role), None) => Ok(role),
+            _ => Err(PieceError::InvalidPiece(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Piece {
+    pub color: Color,
+    pub role: Role,
+}
+
+impl Piece {
+    pub const fn new(color: Color, role: Role) -> Piece {
+        Piece { color, role }
+    }
+
+    // FEN letters, upper case for white
+    pub fn to_char(self) -> char {
+        match self.color {
+            Color::White => self.role.to_char().to_ascii_uppercase(),
+            Color::Black => self.role.to_char(),
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Piece> {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Role::from_char(c).map(|role| Piece::new(color, role))
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl FromStr for Piece {
+    type Err = PieceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Piece::from_char), chars.next()) {
+            (Some(piece), None) => Ok(piece),
+            _ => Err(PieceError::InvalidPiece(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PieceError {
+    InvalidColor(String),
+    InvalidPiece(String),
+}
+
+impl fmt::Display for PieceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PieceError::InvalidColor(color) => write!(f, "Invalid color: {}", color),
+            PieceError::InvalidPiece(piece) => write!(f, "Invalid piece: {}", piece),
+        }
+    }
+}
+
+impl Error for PieceError {}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Pieces {
     WPawn,
@@ -54,21 +239,46 @@ impl Pieces {
         )
     }
 
-    pub fn piece_type(&self) -> PieceType {
+    pub fn new(piece: Piece) -> Pieces {
+        match (piece.color, piece.role) {
+            (Color::White, Role::Pawn) => Pieces::WPawn,
+            (Color::White, Role::Knight) => Pieces::WKnight,
+            (Color::White, Role::Bishop) => Pieces::WBishop,
+            (Color::White, Role::Rook) => Pieces::WRook,
+            (Color::White, Role::Queen) => Pieces::WQueen,
+            (Color::White, Role::King) => Pieces::WKing,
+            (Color::Black, Role::Pawn) => Pieces::BPawn,
+            (Color::Black, Role::Knight) => Pieces::BKnight,
+            (Color::Black, Role::Bishop) => Pieces::BBishop,
+            (Color::Black, Role::Rook) => Pieces::BRook,
+            (Color::Black, Role::Queen) => Pieces::BQueen,
+            (Color::Black, Role::King) => Pieces::BKing,
+        }
+    }
+
+    // None for Pieces::Empty
+    pub fn piece(&self) -> Option<Piece> {
+        let color = if self.is_white() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        self.role().map(|role| Piece::new(color, role))
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.piece().map(|piece| piece.color)
+    }
+
+    pub fn role(&self) -> Option<Role> {
         match self {
-            Pieces::WPawn => PieceType::Pawn,
-            Pieces::WKnight => PieceType::Knight,
-            Pieces::WBishop => PieceType::Bishop,
-            Pieces::WRook => PieceType::Rook,
-            Pieces::WQueen => PieceType::Queen,
-            Pieces::WKing => PieceType::King,
-            Pieces::BPawn => PieceType::Pawn,
-            Pieces::BKnight => PieceType::Knight,
-            Pieces::BBishop => PieceType::Bishop,
-            Pieces::BRook => PieceType::Rook,
-            Pieces::BQueen => PieceType::Queen,
-            Pieces::BKing => PieceType::King,
-            _ => PieceType::Empty,
+            Pieces::WPawn | Pieces::BPawn => Some(Role::Pawn),
+            Pieces::WKnight | Pieces::BKnight => Some(Role::Knight),
+            Pieces::WBishop | Pieces::BBishop => Some(Role::Bishop),
+            Pieces::WRook | Pieces::BRook => Some(Role::Rook),
+            Pieces::WQueen | Pieces::BQueen => Some(Role::Queen),
+            Pieces::WKing | Pieces::BKing => Some(Role::King),
+            Pieces::Empty => None,
         }
     }
 
@@ -91,27 +301,11 @@ impl Pieces {
     }
 
     pub fn piece_by_color_bb_index(&self) -> usize {
-        match self.piece_type() {
-            PieceType::Pawn => PAWNS_BB,
-            PieceType::Knight => KNIGHTS_BB,
-            PieceType::Bishop => BISHOPS_BB,
-            PieceType::Rook => ROOKS_BB,
-            PieceType::Queen => QUEENS_BB,
-            PieceType::King => KINGS_BB,
-            PieceType::Empty => EMPTY_SQUARES_BB,
-        }
+        self.role().map_or(EMPTY_SQUARES_BB, Role::index)
     }
 
     pub fn combined_color_bb_index(&self) -> usize {
-        match self.piece_type() {
-            PieceType::Pawn => ALL_PAWNS_BB,
-            PieceType::Knight => ALL_KNIGHTS_BB,
-            PieceType::Bishop => ALL_BISHOPS_BB,
-            PieceType::Rook => ALL_ROOKS_BB,
-            PieceType::Queen => ALL_QUEENS_BB,
-            PieceType::King => ALL_KINGS_BB,
-            PieceType::Empty => EMPTY_SQUARES_BB,
-        }
+        self.role().map_or(EMPTY_SQUARES_BB, Role::index)
     }
 }
 
@@ -138,3 +332,51 @@ impl fmt::Display for Pieces {
         )
     }
 }
+
+impl From<Piece> for Pieces {
+    fn from(piece: Piece) -> Self {
+        Pieces::new(piece)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod color {
+        use super::*;
+
+        #[test]
+        fn it_flips_sides() {
+            assert_eq!(!Color::White, Color::Black);
+            assert_eq!(Color::Black.other(), Color::White);
+            assert_eq!("b".parse::<Color>(), Ok(Color::Black));
+            assert_eq!(Color::White.to_string(), "w");
+        }
+    }
+
+    mod piece {
+        use super::*;
+
+        #[test]
+        fn it_round_trips_fen_letters() {
+            for c in "PNBRQKpnbrqk".chars() {
+                assert_eq!(Piece::from_char(c).unwrap().to_char(), c);
+            }
+            assert_eq!(
+                "n".parse::<Piece>(),
+                Ok(Piece::new(Color::Black, Role::Knight))
+            );
+            assert!("x".parse::<Piece>().is_err());
+        }
+
+        #[test]
+        fn it_converts_to_and_from_pieces() {
+            let piece = Piece::new(Color::White, Role::Queen);
+            assert_eq!(Pieces::from(piece), Pieces::WQueen);
+            assert_eq!(Pieces::WQueen.piece(), Some(piece));
+            assert_eq!(Pieces::BRook.color(), Some(Color::Black));
+            assert_eq!(Pieces::Empty.role(), None);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+extern crate bitboard;
+use crate::bitboard::Square;
+extern crate constants;
+use crate::constants::*;
+extern crate piece;
+use crate::piece::{Color, Piece};
+
+mod score;
+pub use score::Score;
+
+// The phase of a position with every piece still on the board. Each knight and bishop counts 1
+// towards it, each rook 2 and each queen 4, so only kings and pawns make a phase of 0
+pub const MAX_PHASE: i32 = 24;
+
+// Indexed by Role
+pub const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+// Tables are written the way the board is drawn, from a8 in the top left to h1 in the bottom right,
+// and hold the bonus for a white piece on each square. Black reads them with the ranks flipped.
+// The middlegame values follow https://www.chessprogramming.org/Simplified_Evaluation_Function
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+// Once the pieces are gone, every step closer to promotion counts
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     80,  80,  80,  80,  80,  80,  80,  80,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     30,  30,  30,  30,  30,  30,  30,  30,
+     20,  20,  20,  20,  20,  20,  20,  20,
+     10,  10,  10,  10,  10,  10,  10,  10,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+// The king hides behind its pawns while there are pieces around to attack it
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+// and heads for the center once they are gone
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+// Indexed by Role
+const MG_TABLES: [&[i32; 64]; 6] = [&PAWN_MG, &KNIGHT, &BISHOP, &ROOK, &QUEEN, &KING_MG];
+const EG_TABLES: [&[i32; 64]; 6] = [&PAWN_EG, &KNIGHT, &BISHOP, &ROOK, &QUEEN, &KING_EG];
+
+// The material value of every role, the same in both phases
+pub const MATERIAL: [Score; 6] = [
+    Score::new(PAWN_VALUE as i32, PAWN_VALUE as i32),
+    Score::new(KNIGHT_VALUE as i32, KNIGHT_VALUE as i32),
+    Score::new(BISHOP_VALUE as i32, BISHOP_VALUE as i32),
+    Score::new(ROOK_VALUE as i32, ROOK_VALUE as i32),
+    Score::new(QUEEN_VALUE as i32, QUEEN_VALUE as i32),
+    Score::new(0, 0),
+];
+
+// The bonus #piece gets for standing on #square, on top of its MATERIAL value
+pub fn psqt(piece: Piece, square: Square) -> Score {
+    // Squares count up from a1 but the tables start at a8, so white flips the rank to look one up
+    let idx = match piece.color {
+        Color::White => square.index() ^ 56,
+        Color::Black => square.index(),
+    };
+    let role = piece.role.index();
+
+    Score::new(MG_TABLES[role][idx], EG_TABLES[role][idx])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::Role;
+
+    mod psqt {
+        use super::*;
+
+        #[test]
+        fn it_prefers_central_pieces() {
+            let knight = Piece::new(Color::White, Role::Knight);
+            assert!(psqt(knight, Square::E4).mg > psqt(knight, Square::A1).mg);
+
+            // The king shelters in the middlegame but is active in the endgame
+            let king = Piece::new(Color::Black, Role::King);
+            let (corner, center) = (psqt(king, Square::G8), psqt(king, Square::E5));
+            assert!(corner.mg > center.mg);
+            assert!(corner.eg < center.eg);
+        }
+
+        #[test]
+        fn it_reads_the_tables_from_each_side() {
+            for role in Role::ALL.iter() {
+                let white = Piece::new(Color::White, *role);
+                let black = Piece::new(Color::Black, *role);
+
+                for square in Square::all() {
+                    let mirrored = Square::new(square.index() as u8 ^ 56);
+                    assert_eq!(psqt(white, square), psqt(black, mirrored));
+                }
+            }
+        }
+    }
+
+    mod score {
+        use super::*;
+
+        #[test]
+        fn it_tapers_between_the_phases() {
+            let score = Score::new(100, 20);
+
+            assert_eq!(score.taper(MAX_PHASE), 100);
+            assert_eq!(score.taper(0), 20);
+            assert_eq!(score.taper(MAX_PHASE / 2), 60);
+            assert_eq!((-score).taper(MAX_PHASE / 2), -60);
+        }
+    }
+}
@@ -0,0 +1,565 @@
+extern crate board;
+use crate::board::{Board, UndoInfo};
+extern crate chessmove;
+use crate::chessmove::{ChessMove, Move};
+extern crate constants;
+use crate::constants::*;
+extern crate eval;
+use crate::eval::{Classical, Evaluator};
+extern crate movegen;
+use crate::movegen::MoveGen;
+extern crate piece;
+use crate::piece::Role;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const INFINITY: i32 = 1_000_000;
+pub const MATE_SCORE: i32 = 100_000;
+pub const MAX_DEPTH: u8 = 64;
+
+// How many nodes are searched between checks of the clock and the stop flag
+const CHECK_INTERVAL: u64 = 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> Self {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..SearchLimits::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Option<ChessMove>,
+    // Centipawns from the perspective of the side to move
+    pub score: i32,
+    pub depth: u8,
+    pub nodes: u64,
+    pub pv: Vec<ChessMove>,
+}
+
+// Positions are scored by #E, which keeps whatever it caches from one search to the next
+pub struct Search<E = Classical> {
+    limits: SearchLimits,
+    stop: Arc<AtomicBool>,
+    evaluator: E,
+}
+
+impl Search {
+    pub fn new(limits: SearchLimits) -> Self {
+        Search::with_evaluator(limits, Classical::default())
+    }
+}
+
+impl<E: Evaluator> Search<E> {
+    pub fn with_evaluator(limits: SearchLimits, evaluator: E) -> Self {
+        Search {
+            limits,
+            stop: Arc::new(AtomicBool::new(false)),
+            evaluator,
+        }
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    pub fn into_evaluator(self) -> E {
+        self.evaluator
+    }
+
+    // Setting the returned flag from another thread ends the search after the current node. The
+    // flag is cleared once that search returns, so the next one runs to its limits again. A stop
+    // that arrives before the search starts still ends it
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    // Iterative deepening: each completed depth replaces the result of the one before it,
+    // and an iteration cut short by a limit is thrown away
+    pub fn search(&mut self, board: &Board) -> SearchResult {
+        self.search_with_info(board, |_| {})
+    }
+
+    // Same as #search, but #on_iteration is called with the result of every completed depth
+    pub fn search_with_info<F: FnMut(&SearchResult)>(
+        &mut self,
+        board: &Board,
+        mut on_iteration: F,
+    ) -> SearchResult {
+        let mut board = *board;
+        MoveGen::update_derived_bitboards(&mut board);
+
+        let mut searcher = Searcher {
+            board,
+            limits: self.limits,
+            stop: &self.stop,
+            start: Instant::now(),
+            nodes: 0,
+            aborted: false,
+            prev_pv: Vec::new(),
+            evaluator: &mut self.evaluator,
+        };
+
+        // A limit can cut the first iteration short, and there is always a legal move to play then
+        let mut root_moves = MoveGen::legal_moves(&board);
+        searcher.order_moves(&board, &mut root_moves, 0);
+
+        let mut result = SearchResult {
+            best_move: root_moves.first().map(|m| ChessMove::from(*m)),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+
+        let max_depth = self.limits.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = searcher.negamax(depth, 0, -INFINITY, INFINITY, &mut pv);
+
+            if searcher.aborted {
+                break;
+            }
+
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score,
+                depth,
+                nodes: searcher.nodes,
+                pv: pv.clone(),
+            };
+            searcher.prev_pv = pv;
+            on_iteration(&result);
+
+            // No line can be improved on once a forced mate has been found
+            if score.abs() >= MATE_SCORE - MAX_DEPTH as i32 || result.best_move.is_none() {
+                break;
+            }
+        }
+
+        result.nodes = searcher.nodes;
+        self.stop.store(false, Ordering::Relaxed);
+        result
+    }
+}
+
+// Moves are played on the board itself rather than through a Game, and generated into lists on the
+// stack, so the search only allocates for principal variations
+struct Searcher<'a, E> {
+    board: Board,
+    limits: SearchLimits,
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+    aborted: bool,
+    prev_pv: Vec<ChessMove>,
+    evaluator: &'a mut E,
+}
+
+impl<'a, E: Evaluator> Searcher<'a, E> {
+    fn negamax(
+        &mut self,
+        depth: u8,
+        ply: u8,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<ChessMove>,
+    ) -> i32 {
+        // Quiescence generates its own moves and spots mate and stalemate with them
+        if depth == 0 {
+            return self.quiescence(alpha, beta, ply);
+        }
+
+        if self.should_abort() {
+            return 0;
+        }
+
+        let board = self.board;
+        let mut moves = MoveGen::legal_moves(&board);
+
+        if moves.is_empty() {
+            // Prefer the quickest mate, and the slowest one when being mated
+            return if board.checkers.is_not_empty() {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        self.order_moves(&board, &mut moves, ply);
+
+        for m in moves.iter() {
+            let chessmove = ChessMove::from(*m);
+            let mut child_pv = Vec::new();
+
+            let undo = self.make_move(&chessmove);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            self.unmake_move(&chessmove, undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(chessmove);
+                pv.append(&mut child_pv);
+            }
+        }
+
+        alpha
+    }
+
+    // Only captures and promotions are played out, so the static evaluation is never taken
+    // in the middle of an exchange. Those that lose material by Board#see are skipped
+    fn quiescence(&mut self, mut alpha: i32, beta: i32, ply: u8) -> i32 {
+        if self.should_abort() {
+            return 0;
+        }
+
+        let board = self.board;
+        let in_check = board.checkers.is_not_empty();
+        // When in check, standing pat is not an option, so every evasion is considered
+        let mut moves = if in_check {
+            MoveGen::evasions(&board)
+        } else {
+            MoveGen::captures(&board)
+        };
+
+        if moves.is_empty() {
+            if in_check {
+                return -MATE_SCORE + ply as i32;
+            }
+            // Running out of captures is common, only without quiet moves as well is it stalemate
+            if MoveGen::quiets(&board).is_empty() {
+                return 0;
+            }
+        }
+
+        if !in_check {
+            let stand_pat = self.evaluator.evaluate(&board);
+            if stand_pat >= beta {
+                return beta;
+            }
+            if stand_pat > alpha {
+                alpha = stand_pat;
+            }
+
+            moves.retain(|m| board.see_ge(m, 0));
+        }
+
+        self.order_moves(&board, &mut moves, MAX_DEPTH);
+
+        for m in moves.iter() {
+            let chessmove = ChessMove::from(*m);
+
+            let undo = self.make_move(&chessmove);
+            let score = -self.quiescence(-beta, -alpha, ply + 1);
+            self.unmake_move(&chessmove, undo);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        alpha
+    }
+
+    // The previous iteration's principal variation is searched first, then captures ordered by
+    // most valuable victim / least valuable attacker, then everything else
+    fn order_moves(&self, board: &Board, moves: &mut [Move], ply: u8) {
+        let pv_move = self.prev_pv.get(ply as usize).copied();
+
+        moves.sort_by_cached_key(|m| {
+            if Some(ChessMove::from(*m)) == pv_move {
+                return i32::MIN;
+            }
+
+            let mut score = 0;
+            if m.is_capture() {
+                // The pawn taken en passant is not on the destination square
+                let victim = board.get_piece_at(m.to_square()).role();
+                let attacker = board.get_piece_at(m.from_square()).role();
+                score -= 10 * piece_value(victim.unwrap_or(Role::Pawn))
+                    - attacker.map_or(0, piece_value);
+            }
+            if let Some(promotion) = m.promotion() {
+                score -= piece_value(promotion);
+            }
+            score
+        });
+    }
+
+    fn make_move(&mut self, chessmove: &ChessMove) -> UndoInfo {
+        let undo = self.board.make_move(chessmove);
+        MoveGen::update_derived_bitboards(&mut self.board);
+        self.evaluator.on_make_move(&self.board, chessmove);
+        undo
+    }
+
+    fn unmake_move(&mut self, chessmove: &ChessMove, undo: UndoInfo) {
+        self.board.unmake_move(chessmove, undo);
+        self.evaluator.on_unmake_move(&self.board, chessmove);
+    }
+
+    fn should_abort(&mut self) -> bool {
+        self.nodes += 1;
+
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes > nodes {
+                self.aborted = true;
+            }
+        }
+
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            if self.stop.load(Ordering::Relaxed) {
+                self.aborted = true;
+            }
+
+            if let Some(movetime) = self.limits.movetime {
+                if self.start.elapsed() >= movetime {
+                    self.aborted = true;
+                }
+            }
+        }
+
+        self.aborted
+    }
+}
+
+fn piece_value(role: Role) -> i32 {
+    (match role {
+        Role::Pawn => PAWN_VALUE,
+        Role::Knight => KNIGHT_VALUE,
+        Role::Bishop => BISHOP_VALUE,
+        Role::Rook => ROOK_VALUE,
+        Role::Queen => QUEEN_VALUE,
+        Role::King => QUEEN_VALUE * 10,
+    }) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitboard::Square;
+
+    mod search {
+        use super::*;
+
+        #[test]
+        fn it_finds_mate_in_one() {
+            let b = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+            let result = Search::new(SearchLimits::depth(3)).search(&b);
+
+            assert_eq!(
+                result.best_move,
+                Some(ChessMove::new(Square::A1, Square::A8))
+            );
+            assert_eq!(result.score, MATE_SCORE - 1);
+        }
+
+        #[test]
+        fn it_takes_a_hanging_queen() {
+            let b = Board::from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+            let result = Search::new(SearchLimits::depth(2)).search(&b);
+
+            assert_eq!(
+                result.best_move,
+                Some(ChessMove::new(Square::D2, Square::D5))
+            );
+            assert_eq!(result.pv.first(), result.best_move.as_ref());
+        }
+
+        #[test]
+        fn it_sees_a_defended_piece_in_quiescence() {
+            // The knight on e5 is defended, so taking it with the queen loses material
+            let b = Board::from_fen("4k3/8/3p4/4n3/8/8/4Q3/4K3 w - - 0 1");
+            let result = Search::new(SearchLimits::depth(1)).search(&b);
+
+            assert_ne!(
+                result.best_move,
+                Some(ChessMove::new(Square::E2, Square::E5))
+            );
+        }
+
+        #[test]
+        fn it_returns_no_move_when_mated() {
+            let b = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+            let result = Search::new(SearchLimits::depth(3)).search(&b);
+
+            assert_eq!(result.best_move, None);
+            assert_eq!(result.score, -MATE_SCORE);
+        }
+
+        #[test]
+        fn it_respects_the_depth_limit() {
+            let result = Search::new(SearchLimits::depth(2)).search(&Board::default());
+
+            assert_eq!(result.depth, 2);
+            assert_eq!(result.pv.len(), 2);
+        }
+
+        #[test]
+        fn it_reports_every_completed_depth() {
+            let mut depths = Vec::new();
+            Search::new(SearchLimits::depth(3))
+                .search_with_info(&Board::default(), |result| depths.push(result.depth));
+
+            assert_eq!(depths, vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn it_respects_the_node_limit() {
+            let result = Search::new(SearchLimits::nodes(2000)).search(&Board::default());
+
+            assert!(result.nodes <= 2001);
+            assert!(result.best_move.is_some());
+        }
+
+        #[test]
+        fn it_has_a_move_when_the_first_depth_is_cut_short() {
+            let kiwipete = Board::from_fen(
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            );
+            for b in [Board::default(), kiwipete].iter() {
+                let result = Search::new(SearchLimits::nodes(1)).search(b);
+
+                assert_eq!(result.depth, 0);
+                assert!(result.best_move.is_some());
+            }
+        }
+
+        #[test]
+        fn it_can_search_again_after_being_stopped() {
+            // Deep enough to pass the first check of the stop flag
+            let mut search = Search::new(SearchLimits::depth(4));
+            search.stop_handle().store(true, Ordering::Relaxed);
+            assert!(search.search(&Board::default()).depth < 4);
+
+            assert_eq!(search.search(&Board::default()).depth, 4);
+        }
+
+        #[test]
+        fn it_respects_the_time_limit() {
+            let start = Instant::now();
+            let result = Search::new(SearchLimits::movetime(Duration::from_millis(200)))
+                .search(&Board::default());
+
+            assert!(start.elapsed() < Duration::from_secs(2));
+            assert!(result.best_move.is_some());
+        }
+    }
+
+    mod evaluator {
+        use super::*;
+        use crate::eval::{Material, Random};
+
+        // Counts how often each hook is called, and scores every position as even
+        #[derive(Default)]
+        struct Counting {
+            evaluations: u64,
+            makes: u64,
+            unmakes: u64,
+        }
+
+        impl Evaluator for Counting {
+            fn evaluate(&mut self, _board: &Board) -> i32 {
+                self.evaluations += 1;
+                0
+            }
+
+            fn on_make_move(&mut self, _board: &Board, _chessmove: &ChessMove) {
+                self.makes += 1;
+            }
+
+            fn on_unmake_move(&mut self, _board: &Board, _chessmove: &ChessMove) {
+                self.unmakes += 1;
+            }
+        }
+
+        #[test]
+        fn it_finds_mate_whatever_the_evaluation() {
+            let b = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+            let mate = Some(ChessMove::new(Square::A1, Square::A8));
+            let limits = SearchLimits::depth(3);
+
+            assert_eq!(
+                Search::with_evaluator(limits, Material)
+                    .search(&b)
+                    .best_move,
+                mate
+            );
+            assert_eq!(
+                Search::with_evaluator(limits, Random::new(1, 100))
+                    .search(&b)
+                    .best_move,
+                mate
+            );
+
+            let boxed: Box<dyn Evaluator> = Box::new(Material);
+            assert_eq!(
+                Search::with_evaluator(limits, boxed).search(&b).best_move,
+                mate
+            );
+        }
+
+        #[test]
+        fn it_calls_the_move_hooks() {
+            let mut search = Search::with_evaluator(SearchLimits::depth(2), Counting::default());
+            search.search(&Board::default());
+
+            let counting = search.evaluator();
+            assert!(counting.evaluations > 0);
+            assert!(counting.makes > 0);
+            assert_eq!(counting.makes, counting.unmakes);
+        }
+
+        #[test]
+        fn it_keeps_the_evaluator_between_searches() {
+            let mut search = Search::new(SearchLimits::depth(2));
+            search.search(&Board::default());
+            let (_, misses) = search.evaluator().pawn_table().stats();
+
+            search.search(&Board::default());
+            assert_eq!(search.evaluator().pawn_table().stats().1, misses);
+        }
+    }
+}
@@ -1,28 +1,27 @@
 extern crate bitboard;
-use bitboard::BitBoard;
+use bitboard::Square;
 extern crate piece;
 use piece::Pieces;
-extern crate constants;
-use constants::*;
 
+// A square together with whatever stands on it, as laid out by Board::to_array
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Square {
-    pub bitboard: BitBoard,
+pub struct SquareContents {
+    pub square: Square,
     pub piece: Pieces,
 }
 
-impl Default for Square {
+impl Default for SquareContents {
     fn default() -> Self {
-        Square {
-            bitboard: EMPTY,
+        SquareContents {
+            square: Square::A1,
             piece: Pieces::Empty,
         }
     }
 }
 
-impl Square {
-    pub fn new(bitboard: BitBoard, piece: Pieces) -> Self {
-        Square { bitboard, piece }
+impl SquareContents {
+    pub fn new(square: Square, piece: Pieces) -> Self {
+        SquareContents { square, piece }
     }
 }
 
@@ -0,0 +1,614 @@
+extern crate board;
+use crate::board::Board;
+extern crate chessmove;
+use crate::chessmove::ChessMove;
+extern crate eval;
+use crate::eval::{Classical, Evaluator};
+extern crate game;
+use crate::game::Game;
+extern crate movegen;
+use crate::movegen::MoveGen;
+extern crate piece;
+use crate::piece::Color;
+extern crate search;
+use crate::search::{Search, SearchLimits, SearchResult, MATE_SCORE, MAX_DEPTH};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub const ENGINE_NAME: &str = "rust_chess_bitboard";
+pub const ENGINE_AUTHOR: &str = "Robert Taussig";
+
+// Assumed number of moves left in the game when the GUI does not send movestogo
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+// Kept in reserve so that communication overhead does not cause a loss on time
+const MOVE_OVERHEAD: u64 = 50;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Uci,
+    IsReady,
+    UciNewGame,
+    Position {
+        fen: Option<String>,
+        moves: Vec<String>,
+    },
+    Go(GoParams),
+    Stop,
+    Quit,
+}
+
+impl Command {
+    // Returns None for empty lines and for commands the engine does not support, which the
+    // protocol says should be ignored
+    pub fn parse(line: &str) -> Option<Command> {
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next()? {
+            "uci" => Some(Command::Uci),
+            "isready" => Some(Command::IsReady),
+            "ucinewgame" => Some(Command::UciNewGame),
+            "position" => {
+                let tokens: Vec<&str> = tokens.collect();
+                let moves_idx = tokens
+                    .iter()
+                    .position(|token| *token == "moves")
+                    .unwrap_or(tokens.len());
+
+                let fen = match tokens.first() {
+                    Some(&"startpos") => None,
+                    Some(&"fen") => Some(tokens[1..moves_idx].join(" ")),
+                    _ => return None,
+                };
+                let moves = tokens
+                    .iter()
+                    .skip(moves_idx + 1)
+                    .map(|notation| notation.to_string())
+                    .collect();
+
+                Some(Command::Position { fen, moves })
+            }
+            "go" => Some(Command::Go(GoParams::parse(tokens))),
+            "stop" => Some(Command::Stop),
+            "quit" => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+// Times are in milliseconds, as sent by the GUI
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GoParams {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<u64>,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u64>,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    fn parse<'a, I: Iterator<Item = &'a str>>(mut tokens: I) -> Self {
+        let mut params = GoParams::default();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "infinite" => params.infinite = true,
+                "depth" => params.depth = parse_value(tokens.next()),
+                "nodes" => params.nodes = parse_value(tokens.next()),
+                "movetime" => params.movetime = parse_time(tokens.next()),
+                "wtime" => params.wtime = parse_time(tokens.next()),
+                "btime" => params.btime = parse_time(tokens.next()),
+                "winc" => params.winc = parse_time(tokens.next()),
+                "binc" => params.binc = parse_time(tokens.next()),
+                "movestogo" => params.movestogo = parse_value(tokens.next()),
+                _ => (),
+            }
+        }
+
+        params
+    }
+
+    // With a clock, an even share of the remaining time is spent on this move, plus half the increment
+    pub fn to_limits(&self, side_to_move: Color) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            movetime: self.movetime.map(Duration::from_millis),
+        };
+
+        if self.infinite || limits.movetime.is_some() {
+            return limits;
+        }
+
+        let (time, inc) = if side_to_move == Color::White {
+            (self.wtime, self.winc)
+        } else {
+            (self.btime, self.binc)
+        };
+
+        if let Some(time) = time {
+            let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let budget = time / moves_to_go + inc.unwrap_or(0) / 2;
+            let available = time.saturating_sub(MOVE_OVERHEAD).max(1);
+            limits.movetime = Some(Duration::from_millis(budget.clamp(1, available)));
+        }
+
+        limits
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(token: Option<&str>) -> Option<T> {
+    token.and_then(|token| token.parse().ok())
+}
+
+// Some GUIs send negative times once a clock has run out
+fn parse_time(token: Option<&str>) -> Option<u64> {
+    parse_value::<i64>(token).map(|time| time.max(0) as u64)
+}
+
+// Matches #notation against the legal moves of #board, so an illegal move is never returned
+pub fn parse_move(board: &Board, notation: &str) -> Option<ChessMove> {
+    MoveGen::gen_legal_moves(board)
+        .into_iter()
+        .find(|chessmove| chessmove.to_string() == notation.to_lowercase())
+}
+
+pub fn format_info(result: &SearchResult, elapsed: Duration) -> String {
+    let score = if result.score.abs() >= MATE_SCORE - MAX_DEPTH as i32 {
+        let moves = (MATE_SCORE - result.score.abs() + 1) / 2;
+        format!("mate {}", moves * result.score.signum())
+    } else {
+        format!("cp {}", result.score)
+    };
+
+    let millis = elapsed.as_millis() as u64;
+    let nps = result.nodes * 1000 / millis.max(1);
+    let pv: Vec<String> = result.pv.iter().map(ChessMove::to_string).collect();
+
+    format!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        nps,
+        millis,
+        pv.join(" ")
+    )
+}
+
+// #evaluator is handed to the thread of each search and handed back once it stops, so whatever it
+// learns carries over from one search to the next
+pub struct Engine<E = Classical> {
+    game: Game,
+    evaluator: Option<E>,
+    // The stop flag of the running search, and a channel closed on stop that a go infinite search
+    // waits on before sending bestmove
+    search: Option<(Arc<AtomicBool>, Sender<()>, JoinHandle<E>)>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine::with_evaluator(Classical::default())
+    }
+}
+
+impl<E: Evaluator + Send + 'static> Engine<E> {
+    pub fn with_evaluator(evaluator: E) -> Self {
+        Engine {
+            game: Game::new(Board::default()),
+            evaluator: Some(evaluator),
+            search: None,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.game.board
+    }
+
+    // None while a search is running
+    pub fn evaluator(&self) -> Option<&E> {
+        self.evaluator.as_ref()
+    }
+
+    // Returns false once the engine should exit
+    pub fn handle(&mut self, command: Command) -> bool {
+        match command {
+            Command::Uci => {
+                println!("id name {}", ENGINE_NAME);
+                println!("id author {}", ENGINE_AUTHOR);
+                println!("uciok");
+            }
+            Command::IsReady => println!("readyok"),
+            Command::UciNewGame => {
+                self.stop();
+                self.game = Game::new(Board::default());
+                if let Some(evaluator) = self.evaluator.as_mut() {
+                    evaluator.new_game();
+                }
+            }
+            Command::Position { fen, moves } => {
+                self.stop();
+                self.set_position(fen, &moves);
+            }
+            Command::Go(params) => {
+                self.stop();
+                self.go(params);
+            }
+            Command::Stop => self.stop(),
+            Command::Quit => {
+                self.stop();
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn set_position(&mut self, fen: Option<String>, moves: &[String]) {
+        let board = match fen {
+            Some(fen) => match Board::try_from_fen(fen.as_str()) {
+                Ok(board) => board,
+                Err(error) => {
+                    eprintln!("Invalid FEN: {}", error);
+                    return;
+                }
+            },
+            None => Board::default(),
+        };
+        self.game = Game::new(board);
+
+        for notation in moves {
+            match parse_move(&self.game.board, notation) {
+                Some(chessmove) => {
+                    self.game.make_move(&chessmove);
+                }
+                None => {
+                    eprintln!("Illegal move: {}", notation);
+                    break;
+                }
+            }
+        }
+    }
+
+    // The search runs on its own thread so that stop can still be read from stdin
+    fn go(&mut self, params: GoParams) {
+        let board = self.game.board;
+        let evaluator = self
+            .evaluator
+            .take()
+            .expect("The evaluator is only away during a search");
+        let mut search = Search::with_evaluator(params.to_limits(board.side_to_move), evaluator);
+        let stop = search.stop_handle();
+        let (stopped, wait_for_stop) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+            let result = search.search_with_info(&board, |result| {
+                println!("{}", format_info(result, start.elapsed()));
+            });
+
+            // During go infinite, bestmove may only be sent after the GUI has sent stop
+            if params.infinite {
+                wait_for_stop.recv().ok();
+            }
+
+            match result.best_move {
+                Some(chessmove) => println!("bestmove {}", chessmove),
+                None => println!("bestmove 0000"),
+            }
+
+            search.into_evaluator()
+        });
+
+        self.search = Some((stop, stopped, handle));
+    }
+}
+
+impl<E> Engine<E> {
+    fn stop(&mut self) {
+        if let Some((stop, stopped, handle)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            drop(stopped);
+            self.evaluator = Some(handle.join().unwrap());
+        }
+    }
+}
+
+// Also sends bestmove for a search still running when the input ends
+impl<E> Drop for Engine<E> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitboard::Square;
+    use piece::Pieces;
+    use std::sync::atomic::AtomicU64;
+
+    // Shares its count of evaluations with the test
+    #[derive(Default)]
+    struct Counting {
+        evaluations: Arc<AtomicU64>,
+        new_games: u32,
+    }
+
+    impl Evaluator for Counting {
+        fn evaluate(&mut self, _board: &Board) -> i32 {
+            self.evaluations.fetch_add(1, Ordering::Relaxed);
+            0
+        }
+
+        fn new_game(&mut self) {
+            self.new_games += 1;
+        }
+    }
+
+    mod parse_command {
+        use super::*;
+
+        #[test]
+        fn it_parses_startpos_with_moves() {
+            assert_eq!(
+                Command::parse("position startpos moves e2e4 e7e5"),
+                Some(Command::Position {
+                    fen: None,
+                    moves: vec!["e2e4".to_string(), "e7e5".to_string()],
+                })
+            );
+        }
+
+        #[test]
+        fn it_parses_fen_without_moves() {
+            assert_eq!(
+                Command::parse("position fen 4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+                Some(Command::Position {
+                    fen: Some("4k3/8/8/8/8/8/8/4K3 w - - 0 1".to_string()),
+                    moves: Vec::new(),
+                })
+            );
+        }
+
+        #[test]
+        fn it_parses_go() {
+            assert_eq!(
+                Command::parse("go wtime 60000 btime -10 winc 1000 movestogo 20"),
+                Some(Command::Go(GoParams {
+                    wtime: Some(60000),
+                    btime: Some(0),
+                    winc: Some(1000),
+                    movestogo: Some(20),
+                    ..GoParams::default()
+                }))
+            );
+            assert_eq!(
+                Command::parse("go infinite"),
+                Some(Command::Go(GoParams {
+                    infinite: true,
+                    ..GoParams::default()
+                }))
+            );
+        }
+
+        #[test]
+        fn it_ignores_unknown_commands() {
+            assert_eq!(Command::parse("debug on"), None);
+            assert_eq!(Command::parse(""), None);
+        }
+    }
+
+    mod to_limits {
+        use super::*;
+
+        #[test]
+        fn it_budgets_the_clock_of_the_side_to_move() {
+            let params = GoParams {
+                wtime: Some(60000),
+                btime: Some(1000),
+                winc: Some(1000),
+                movestogo: Some(20),
+                ..GoParams::default()
+            };
+
+            assert_eq!(
+                params.to_limits(Color::White).movetime,
+                Some(Duration::from_millis(3500))
+            );
+            assert_eq!(
+                params.to_limits(Color::Black).movetime,
+                Some(Duration::from_millis(50))
+            );
+        }
+
+        #[test]
+        fn it_prefers_an_explicit_movetime() {
+            let params = GoParams {
+                movetime: Some(100),
+                wtime: Some(60000),
+                ..GoParams::default()
+            };
+
+            assert_eq!(
+                params.to_limits(Color::White).movetime,
+                Some(Duration::from_millis(100))
+            );
+        }
+    }
+
+    mod parse_move {
+        use super::*;
+
+        #[test]
+        fn it_works() {
+            let b = Board::default();
+
+            assert_eq!(
+                parse_move(&b, "e2e4"),
+                Some(ChessMove::new(Square::E2, Square::E4))
+            );
+            assert_eq!(parse_move(&b, "e2e5"), None);
+        }
+
+        #[test]
+        fn it_parses_promotions() {
+            let b = Board::from_fen("8/1P6/8/8/8/8/8/k6K w - - 0 1");
+            let chessmove = parse_move(&b, "b7b8n").unwrap();
+
+            assert_eq!(chessmove.promotion, Some(Pieces::WKnight));
+            assert_eq!(chessmove.to_string(), "b7b8n");
+        }
+    }
+
+    mod set_position {
+        use super::*;
+
+        #[test]
+        fn it_plays_the_moves() {
+            let mut engine = Engine::new();
+            let moves = vec!["e2e4".to_string(), "c7c5".to_string(), "g1f3".to_string()];
+            engine.set_position(None, &moves);
+
+            assert_eq!(
+                engine.board().to_fen(),
+                "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+            );
+        }
+
+        #[test]
+        fn it_stops_at_an_illegal_move() {
+            let mut engine = Engine::new();
+            let moves = vec!["e2e4".to_string(), "e2e4".to_string()];
+            engine.set_position(None, &moves);
+
+            assert_eq!(
+                engine.board().to_fen(),
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+            );
+        }
+
+        #[test]
+        fn it_keeps_the_position_on_an_invalid_fen() {
+            let mut engine = Engine::new();
+            engine.set_position(Some(String::from("4k3/8/8/8/8/8/8/4K3 w - - 0 1")), &[]);
+            engine.set_position(Some(String::from("4k3/8/8/8/8/8/8/8 w - - 0 1")), &[]);
+
+            assert_eq!(engine.board().to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        }
+    }
+
+    mod format_info {
+        use super::*;
+
+        #[test]
+        fn it_reports_mate_in_moves() {
+            let result = SearchResult {
+                best_move: Some(ChessMove::new(Square::A1, Square::A8)),
+                score: MATE_SCORE - 1,
+                depth: 1,
+                nodes: 500,
+                pv: vec![ChessMove::new(Square::A1, Square::A8)],
+            };
+
+            assert_eq!(
+                format_info(&result, Duration::from_millis(250)),
+                "info depth 1 score mate 1 nodes 500 nps 2000 time 250 pv a1a8"
+            );
+        }
+
+        #[test]
+        fn it_reports_centipawns() {
+            let result = SearchResult {
+                best_move: None,
+                score: -35,
+                depth: 4,
+                nodes: 10,
+                pv: Vec::new(),
+            };
+
+            assert!(format_info(&result, Duration::from_millis(0)).contains("score cp -35"));
+        }
+    }
+
+    mod evaluator {
+        use super::*;
+
+        #[test]
+        fn it_searches_with_the_given_evaluator() {
+            let mut engine = Engine::with_evaluator(Counting::default());
+            engine.handle(Command::parse("go depth 2").unwrap());
+            engine.handle(Command::Stop);
+
+            assert!(
+                engine
+                    .evaluator()
+                    .unwrap()
+                    .evaluations
+                    .load(Ordering::Relaxed)
+                    > 0
+            );
+        }
+
+        #[test]
+        fn it_keeps_the_evaluator_between_searches() {
+            let mut engine = Engine::new();
+            let misses = |engine: &Engine| engine.evaluator().unwrap().pawn_table().stats().1;
+
+            engine.handle(Command::parse("go depth 2").unwrap());
+            engine.handle(Command::Stop);
+            let after_first_search = misses(&engine);
+            assert!(after_first_search > 0);
+
+            engine.handle(Command::parse("go depth 2").unwrap());
+            engine.handle(Command::Stop);
+            assert_eq!(misses(&engine), after_first_search);
+        }
+
+        #[test]
+        fn it_starts_the_evaluator_on_a_new_game() {
+            let mut engine = Engine::with_evaluator(Counting::default());
+            engine.handle(Command::UciNewGame);
+
+            assert_eq!(engine.evaluator().unwrap().new_games, 1);
+        }
+    }
+
+    mod engine {
+        use super::*;
+
+        #[test]
+        fn it_waits_for_stop_during_go_infinite() {
+            let mut engine = Engine::new();
+            engine.handle(Command::parse("go infinite").unwrap());
+
+            assert!(engine.handle(Command::Stop));
+            assert!(engine.search.is_none());
+        }
+
+        #[test]
+        fn it_finishes_the_search_when_dropped() {
+            let evaluator = Counting::default();
+            let evaluations = evaluator.evaluations.clone();
+            let mut engine = Engine::with_evaluator(evaluator);
+            engine.handle(Command::parse("go infinite").unwrap());
+            drop(engine);
+
+            // The evaluator is dropped with the engine once the search thread has handed it back
+            assert_eq!(Arc::strong_count(&evaluations), 1);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+extern crate uci;
+use uci::*;
+
+use std::io::{self, BufRead};
+
+// Speaks the Universal Chess Interface over stdin/stdout, so the engine can be driven by a GUI
+fn main() {
+    let mut engine = Engine::new();
+
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        if let Some(command) = Command::parse(&line) {
+            if !engine.handle(command) {
+                break;
+            }
+        }
+    }
+}
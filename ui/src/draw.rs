@@ -41,7 +41,7 @@ pub fn draw_border(ctx: &mut ggez::Context, points: &[Point2<f32>]) -> ggez::Gam
     let mesh = Mesh::new_polygon(
         ctx,
         DrawMode::stroke(BORDER_WIDTH),
-        &points,
+        points,
         PIECE_BORDER_COLOR,
     )
     .expect("error building piece");
@@ -283,7 +283,7 @@ pub fn draw_queen(ctx: &mut ggez::Context, x: f32, y: f32, color: Color) -> ggez
             y,
         },
         Point2 {
-            x: x,
+            x,
             y: y - SQUARE_SIZE * 0.42,
         },
         Point2 {
@@ -378,6 +378,7 @@ pub fn draw_last_move_border(ctx: &mut ggez::Context, row: usize, col: usize) ->
     draw(ctx, &mesh, DrawParam::default())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_arbitrary_rectangle(
     ctx: &mut ggez::Context,
     x: f32,
@@ -391,7 +392,7 @@ pub fn draw_arbitrary_rectangle(
     let rect = Rect::new(x, y, w, h);
     let mesh = Mesh::new_rectangle(ctx, draw_mode.unwrap_or(DrawMode::fill()), rect, color)
         .expect("error creating rect");
-    draw(ctx, &mesh, draw_params.unwrap_or(DrawParam::default()))
+    draw(ctx, &mesh, draw_params.unwrap_or_default())
 }
 
 pub fn draw_move_from_border(ctx: &mut ggez::Context, row: usize, col: usize) -> ggez::GameResult {
@@ -426,12 +427,7 @@ pub fn draw_square(
 ) -> ggez::GameResult {
     let is_white = row % 2 == col % 2;
     let rect = rect_from_row_and_col(row, col);
-    let color: Color;
-    if is_white {
-        color = WHITE_SQUARE;
-    } else {
-        color = BLACK_SQUARE;
-    }
+    let color = if is_white { WHITE_SQUARE } else { BLACK_SQUARE };
 
     let mesh =
         Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color).expect("error creating rect");
@@ -1,11 +1,11 @@
-#[macro_use]
-extern crate lazy_static;
 extern crate game;
 use board::Board;
 use clipboard::{ClipboardContext, ClipboardProvider};
+use game::pgn::{read_pgn, write_pgn};
 use game::Game;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::rc::Rc;
 extern crate piece;
 use piece::Pieces;
@@ -57,15 +57,15 @@ impl MainState {
 
     fn is_valid_destination(&self, to: BitBoard) -> bool {
         self.valid_moves.iter().any(|chessmove| {
-            ((chessmove.from & self.move_from).is_not_empty())
-                && ((chessmove.to & to).is_not_empty())
+            ((chessmove.from.bitboard() & self.move_from).is_not_empty())
+                && ((chessmove.to.bitboard() & to).is_not_empty())
         })
     }
 
     fn go_back(&mut self) {
         if self.game.history.len() > 1 {
             let prev_board = self.game.board;
-            let last_move = self.game.go_back();
+            let last_move = last_move_bitboards(self.game.go_back());
 
             self.move_pieces_between_game_boards(&prev_board, &self.game.board);
             let valid_moves = MoveGen::gen_legal_moves(&self.game.board);
@@ -77,9 +77,9 @@ impl MainState {
     }
 
     fn go_forward(&mut self) {
-        if self.game.future.len() > 0 {
+        if !self.game.future.is_empty() {
             let prev_board = self.game.board;
-            let last_move = self.game.go_forward();
+            let last_move = last_move_bitboards(self.game.go_forward());
 
             self.move_pieces_between_game_boards(&prev_board, &self.game.board);
             let valid_moves = MoveGen::gen_legal_moves(&self.game.board);
@@ -105,18 +105,21 @@ impl MainState {
             print!("Valid moves");
             for i in 0..self.valid_moves.len() {
                 let cm = self.valid_moves[i];
-                cm.from.print_bb("From");
-                cm.to.print_bb("To");
+                cm.from.bitboard().print_bb("From");
+                cm.to.bitboard().print_bb("To");
             }
         }
     }
 
     fn make_move(&mut self, from: BitBoard, to: BitBoard) {
-        let moving_piece = self.game.board.get_piece_at(from);
-        if moving_piece == Pieces::WPawn && (to & RANK_8).is_not_empty() {
-            self.handle_white_promotion(from, to);
-        } else if moving_piece == Pieces::BPawn && (to & RANK_1).is_not_empty() {
-            self.handle_black_promotion(from, to);
+        let is_promotion = self.valid_moves.iter().any(|chessmove| {
+            chessmove.from.bitboard() == from
+                && (chessmove.to.bitboard() & to).is_not_empty()
+                && chessmove.promotion.is_some()
+        });
+
+        if is_promotion {
+            self.handle_promotion(from, to);
         } else {
             self.commit_move(from, to);
         }
@@ -124,17 +127,18 @@ impl MainState {
     }
 
     fn commit_move(&mut self, from: BitBoard, to: BitBoard) {
-        let moves = self.game.make_move(&ChessMove::new(from, to));
+        let chessmove = ChessMove::new(to_square(from), to_square(to));
+        let moves = self.game.make_move(&chessmove);
         self.valid_moves = MoveGen::gen_legal_moves(&self.game.board);
         self.last_move = (from, to);
         let mut moving_pieces = self.moving_pieces.borrow_mut();
         moves.iter().for_each(|move_tuple| {
             moving_pieces.insert(
-                move_tuple.1,
+                move_tuple.1.bitboard(),
                 MovingPiece::new(
                     self.game.board.get_piece_at(move_tuple.1),
-                    move_tuple.0,
-                    move_tuple.1,
+                    move_tuple.0.bitboard(),
+                    move_tuple.1.bitboard(),
                     SQUARE_SIZE,
                     20,
                 ),
@@ -142,22 +146,24 @@ impl MainState {
         });
     }
 
-    fn handle_white_promotion(&mut self, from: BitBoard, to: BitBoard) {
-        self.promotion_panel = Some(PromotionUI::new(from, to, true));
+    fn handle_promotion(&mut self, from: BitBoard, to: BitBoard) {
+        let is_white = self.game.board.side_to_move == piece::Color::White;
+        self.promotion_panel = Some(PromotionUI::new(from, to, is_white));
     }
 
     fn commit_promotion(&mut self, from: BitBoard, to: BitBoard, piece: Pieces) {
-        let moves = self.game.make_move(&ChessMove::promote(from, to, piece));
+        let chessmove = ChessMove::promote(to_square(from), to_square(to), piece);
+        let moves = self.game.make_move(&chessmove);
         self.valid_moves = MoveGen::gen_legal_moves(&self.game.board);
         self.last_move = (from, to);
         let mut moving_pieces = self.moving_pieces.borrow_mut();
         moves.iter().for_each(|move_tuple| {
             moving_pieces.insert(
-                move_tuple.1,
+                move_tuple.1.bitboard(),
                 MovingPiece::new(
                     self.game.board.get_piece_at(move_tuple.1),
-                    move_tuple.0,
-                    move_tuple.1,
+                    move_tuple.0.bitboard(),
+                    move_tuple.1.bitboard(),
                     SQUARE_SIZE,
                     20,
                 ),
@@ -165,10 +171,6 @@ impl MainState {
         });
     }
 
-    fn handle_black_promotion(&mut self, from: BitBoard, to: BitBoard) {
-        self.promotion_panel = Some(PromotionUI::new(from, to, false));
-    }
-
     fn restart_from_fen(&mut self, fen: &str) {
         let prev_board = self.game.board;
         self.game.restart_from_fen(fen);
@@ -176,12 +178,15 @@ impl MainState {
         self.restart_game();
     }
 
+    fn load_game(&mut self, game: Game) {
+        let prev_board = self.game.board;
+        self.game = game;
+        self.move_pieces_between_game_boards(&prev_board, &self.game.board);
+        self.restart_game();
+    }
+
     fn restart_game(&mut self) {
-        let (checkers, pinned, attacked_squares) =
-            MoveGen::calculate_derived_bitboards(&self.game.board);
-        self.game.board.attacked_squares = attacked_squares;
-        self.game.board.checkers = checkers;
-        self.game.board.pinned = pinned;
+        MoveGen::update_derived_bitboards(&mut self.game.board);
 
         let valid_moves = MoveGen::gen_legal_moves(&self.game.board);
 
@@ -230,7 +235,7 @@ impl MainState {
         let mut moving_pieces = self.moving_pieces.borrow_mut();
         moving_pieces.iter_mut().for_each(|(bitboard, piece)| {
             piece.update();
-            if piece.done == true {
+            if piece.done {
                 to_remove.push(*bitboard);
             }
         });
@@ -249,8 +254,8 @@ impl MainState {
 
         new_pieces.iter().for_each(|row| {
             row.iter().for_each(|square| {
-                let search_pointer_x = square.bitboard.col();
-                let search_pointer_y = square.bitboard.row();
+                let search_pointer_x = square.square.bitboard().col();
+                let search_pointer_y = square.square.bitboard().row();
 
                 if old_pieces[search_pointer_y][search_pointer_x].piece == square.piece {
                     found_pieces[search_pointer_y][search_pointer_x] = true;
@@ -262,38 +267,39 @@ impl MainState {
             row.iter().for_each(|square| {
                 if square.piece != Pieces::Empty {
                     let mut searched = [[false; 8]; 8];
-                    let search_pointer_x = square.bitboard.col();
-                    let search_pointer_y = square.bitboard.row();
-                    if found_pieces[search_pointer_y][search_pointer_x] == false {
+                    let search_pointer_x = square.square.bitboard().col();
+                    let search_pointer_y = square.square.bitboard().row();
+                    if !found_pieces[search_pointer_y][search_pointer_x] {
                         let mut found_piece: BitBoard = EMPTY;
                         searched[search_pointer_y][search_pointer_x] = true;
                         let mut to_search = vec![(search_pointer_x, search_pointer_y)];
-                        while to_search.len() > 0 {
+                        while !to_search.is_empty() {
                             let (x, y) = to_search.remove(0);
-                            if searched[y][x] == false {
+                            if !searched[y][x] {
                                 searched[y][x] = true;
-                                if found_pieces[y][x] == false
-                                    && old_pieces[y][x].piece == square.piece
-                                {
-                                    found_piece = old_pieces[y][x].bitboard;
+                                if !found_pieces[y][x] && old_pieces[y][x].piece == square.piece {
+                                    found_piece = old_pieces[y][x].square.bitboard();
                                     break;
                                 }
                             }
                             for (x_dir, y_dir) in SEARCH_DIRS.iter() {
                                 let next_x = x as i8 + x_dir;
                                 let next_y = y as i8 + y_dir;
-                                if next_x >= 0 && next_y >= 0 && next_x <= 7 && next_y <= 7 {
-                                    if searched[next_y as usize][next_x as usize] == false {
-                                        to_search.push((next_x as usize, next_y as usize));
-                                    }
+                                if next_x >= 0
+                                    && next_y >= 0
+                                    && next_x <= 7
+                                    && next_y <= 7
+                                    && !searched[next_y as usize][next_x as usize]
+                                {
+                                    to_search.push((next_x as usize, next_y as usize));
                                 }
                             }
                         }
                         if found_piece.is_not_empty() {
                             found_pieces[found_piece.row()][found_piece.col()] = true;
-                            movers.insert(square.bitboard, found_piece);
+                            movers.insert(square.square.bitboard(), found_piece);
                         } else {
-                            movers.insert(square.bitboard, EMPTY);
+                            movers.insert(square.square.bitboard(), EMPTY);
                         }
                     }
                 }
@@ -306,7 +312,7 @@ impl MainState {
             moving_pieces.insert(
                 *to,
                 MovingPiece::new(
-                    new_game_board.get_piece_at(*to),
+                    new_game_board.get_piece_at(to_square(*to)),
                     *from,
                     *to,
                     SQUARE_SIZE,
@@ -317,12 +323,15 @@ impl MainState {
     }
 }
 
-pub fn is_fen(fen: &String) -> bool {
-    use regex::Regex;
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"\s*([rnbqkpRNBQKP1-8]+/){7}([rnbqkpRNBQKP1-8]+)\s[bw-]\s(([a-hkqA-HKQ]{1,4})|(-))\s(([a-h][36])|(-))\s\d+\s\d+\s*").unwrap();
-    }
-    RE.is_match(fen.as_str())
+// The UI keeps track of squares as bitboards, which always hold exactly one square
+fn to_square(square: BitBoard) -> Square {
+    Square::try_from(square).expect("Not a single square")
+}
+
+fn last_move_bitboards(last_move: Option<(Square, Square)>) -> (BitBoard, BitBoard) {
+    last_move.map_or((EMPTY, EMPTY), |(from, to)| {
+        (from.bitboard(), to.bitboard())
+    })
 }
 
 impl EventHandler for MainState {
@@ -384,7 +393,7 @@ impl EventHandler for MainState {
             self.make_move(self.move_from, destination);
         } else {
             self.move_from = destination;
-            let moving_piece = self.game.board.get_piece_at(self.move_from);
+            let moving_piece = self.game.board.get_piece_at(to_square(self.move_from));
             if moving_piece != Pieces::Empty {
                 self.dragged_piece = Some((destination, moving_piece, (x, y)));
             }
@@ -394,53 +403,46 @@ impl EventHandler for MainState {
 
     fn key_up_event(&mut self, _ctx: &mut ggez::Context, keycode: KeyCode, keymods: KeyMods) {
         match keycode {
-            KeyCode::C => {
-                if keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO) {
-                    let mut cp: ClipboardContext = ClipboardProvider::new().unwrap();
+            KeyCode::C if (keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO)) => {
+                let mut cp: ClipboardContext = ClipboardProvider::new().unwrap();
+                if keymods.contains(KeyMods::SHIFT) {
+                    let pgn = write_pgn(&self.game, &[]);
+                    cp.set_contents(pgn).expect("Failed to set pgn contents");
+                } else {
                     let fen = self.game.board.to_fen();
                     cp.set_contents(fen).expect("Failed to set fen contents");
                 }
             }
-            KeyCode::V => {
-                if keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO) {
-                    let mut cp: ClipboardContext = ClipboardProvider::new().unwrap();
-                    let contents = cp.get_contents().unwrap();
-                    if is_fen(&contents) {
-                        self.restart_from_fen(contents.as_str());
-                    }
+            KeyCode::V if (keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO)) => {
+                let mut cp: ClipboardContext = ClipboardProvider::new().unwrap();
+                let contents = cp.get_contents().unwrap();
+                if contents.parse::<Board>().is_ok() {
+                    self.restart_from_fen(contents.as_str());
+                } else if let Ok(pgn) = read_pgn(&contents) {
+                    self.load_game(pgn.game);
                 }
             }
-            KeyCode::R => {
-                if keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO) {
-                    self.randomize_board();
-                }
+            KeyCode::R if (keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO)) => {
+                self.randomize_board();
             }
-            KeyCode::N => {
-                if keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO) {
-                    self.new_game();
-                }
+            KeyCode::N if (keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO)) => {
+                self.new_game();
             }
-            KeyCode::Z => {
-                if keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO) {
-                    self.go_back();
-                }
+            KeyCode::Z if (keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO)) => {
+                self.go_back();
             }
-            KeyCode::Y => {
-                if keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO) {
-                    self.go_forward();
-                }
+            KeyCode::Y if (keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO)) => {
+                self.go_forward();
             }
-            KeyCode::D => {
-                if keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO) {
-                    self.debug(keymods.contains(KeyMods::SHIFT));
-                }
+            KeyCode::D if (keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO)) => {
+                self.debug(keymods.contains(KeyMods::SHIFT));
             }
             _ => (),
         }
     }
 
     fn draw(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
-        if self.needs_draw == false {
+        if !self.needs_draw {
             return Ok(());
         }
 
@@ -459,21 +461,20 @@ impl EventHandler for MainState {
             .enumerate()
             .for_each(|(row_idx, row)| {
                 row.iter().enumerate().for_each(|(col_idx, square)| {
-                    let piece: Pieces;
-                    if moving_pieces.contains_key(&square.bitboard) == true {
-                        piece = Pieces::Empty;
+                    let piece = if moving_pieces.contains_key(&square.square.bitboard()) {
+                        Pieces::Empty
                     } else {
-                        piece = square.piece;
-                    }
+                        square.piece
+                    };
                     draw_square(
                         ctx,
                         row_idx,
                         col_idx,
                         &piece,
-                        dragging_from == square.bitboard,
+                        dragging_from == square.square.bitboard(),
                     )
                     .expect("Failed to draw square");
-                    if self.is_valid_destination(square.bitboard) {
+                    if self.is_valid_destination(square.square.bitboard()) {
                         draw_destination(ctx, row_idx, col_idx)
                             .expect("Failed to draw destination");
                     }
@@ -514,7 +515,7 @@ impl EventHandler for MainState {
             promotion_panel
                 .draw(ctx)
                 .expect("Error drawing promotion panel");
-        } else if moving_pieces.len() == 0 && self.dragged_piece == None {
+        } else if moving_pieces.is_empty() && self.dragged_piece.is_none() {
             self.needs_draw = false;
         }
 
@@ -3,7 +3,6 @@ use piece::Pieces;
 
 pub struct MovingPiece {
     pub piece: Pieces,
-    pub bitboard: BitBoard,
     pub pos: (f32, f32),
     to: (f32, f32),
     vel: (f32, f32),
@@ -32,7 +31,6 @@ impl MovingPiece {
 
         MovingPiece {
             piece,
-            bitboard: to_square,
             pos: (from_x, from_y),
             to: (to_x, to_y),
             vel: (
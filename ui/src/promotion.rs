@@ -63,10 +63,8 @@ impl PromotionUI {
 pub fn absolute_difference(left: usize, right: usize) -> usize {
     if left > right {
         left - right
-    } else if right > left {
-        right - left
     } else {
-        0
+        right.saturating_sub(left)
     }
 }
 